@@ -2,10 +2,11 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use winit::event::MouseButton;
 use yoda::event::{ClickEvent, EventListener, EventState};
-use yoda::layer::{Layer, StaticLayer};
+use yoda::layer::{DynamicLayer, Layer};
 use yoda::render_target::RenderTarget;
 use yoda::runtime::native::NativeRuntime;
 use yoda::symbol::CircleSymbol;
+use yoda::Point3;
 
 fn main() {
     let mut runtime = NativeRuntime::new(&|b| b.with_title("Simple yoda map example"));
@@ -15,7 +16,7 @@ fn main() {
         color: [0.0, 0.7, 0.7, 1.0],
         program: None,
     };
-    let layer = Rc::new(RefCell::new(StaticLayer::new(symbol, vec![])));
+    let layer = Rc::new(RefCell::new(DynamicLayer::<Point3, _>::new(symbol)));
 
     let context = runtime.context();
     let map = runtime.map_mut();
@@ -37,12 +38,12 @@ fn main() {
         );
         let mut layer = layer_copy.borrow_mut();
         if let Some(feature) = layer.feature_at_point(&target, cursor_position, map.position()) {
-            layer.remove(feature);
+            layer.remove(&feature);
             return EventState::Final;
         }
 
-        let map_position = map.position().get_map_position(&cursor_position);
-        layer.add([map_position[0], map_position[1], 0.0]);
+        let map_position = e.map_position(map.position());
+        layer.add_geometry([map_position[0], map_position[1], 0.0]);
 
         // returning EventState::Continue allows the next handler to be called
         EventState::Continue
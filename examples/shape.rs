@@ -1,3 +1,4 @@
+use yoda::geometry::Bounds;
 use yoda::layer::StaticLayer;
 
 use yoda::symbol::CircleSymbol;
@@ -7,24 +8,15 @@ use std::rc::Rc;
 use yoda::runtime::native::NativeRuntime;
 
 fn main() {
-    let shape_points = shapefile::reader::ShapeReader::from_path("./examples/data/points_wm.shp")
-        .unwrap()
-        .read()
-        .unwrap();
-    let mut points = vec![];
-    let mut bbox: [f32; 4] = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
-    for shape in shape_points {
-        match shape {
-            shapefile::Shape::Point(p) => {
-                points.push([p.x as f32, p.y as f32, 0.0]);
-                bbox[0] = bbox[0].min(p.x as f32);
-                bbox[1] = bbox[1].min(p.y as f32);
-                bbox[2] = bbox[2].max(p.x as f32);
-                bbox[3] = bbox[3].max(p.y as f32);
-            }
-            _ => {}
-        }
-    }
+    let data = yoda::io::shapefile::read("./examples/data/points_wm.shp").unwrap();
+    let mut points: Vec<_> = data
+        .features
+        .iter()
+        .flat_map(|feature| feature.geometry().points())
+        .copied()
+        .collect();
+
+    let bbox = points.bbox().unwrap();
 
     let width = bbox[2] - bbox[0];
     let height = bbox[3] - bbox[1];
@@ -37,8 +29,8 @@ fn main() {
 
             for z in 0..len {
                 let p = points[z];
-                points.push([p[0] + i as f32 * width, p[1] + j as f32 * height, 0.0]);
-                // points.push([p[0], p[1], j as f32 * 30000.0]);
+                points.push([p[0] + i as f64 * width, p[1] + j as f64 * height, 0.0]);
+                // points.push([p[0], p[1], j as f64 * 30000.0]);
             }
         }
     }
@@ -50,13 +42,12 @@ fn main() {
         color: [0.0, 0.7, 0.7, 1.0],
         program: None,
     };
-    let layer = StaticLayer::new(symbol, points);
+    let layer = StaticLayer::from_geometries(symbol, points);
 
     let mut runtime = NativeRuntime::new(&|b| b.with_title("Shapefile rendering example"));
     let map = runtime.map_mut();
 
-    map.set_center((bbox[2] + bbox[0]) / 2.0, (bbox[3] + bbox[1]) / 2.0);
-    map.set_resolution((bbox[2] - bbox[0]) / 800.0);
+    map.fit_bounds(bbox, 20.0);
 
     map.add_layer(Rc::new(RefCell::new(layer)));
 
@@ -19,7 +19,7 @@ fn main() {
         [200.0, 0.0, 0.0],
         [200.0, 100.0, 0.0],
     ];
-    let line_layer = StaticLayer::new(line_symbol, vec![line]);
+    let line_layer = StaticLayer::from_geometries(line_symbol, vec![line]);
 
     let point_symbol = CircleSymbol {
         size: 20.0,
@@ -32,7 +32,7 @@ fn main() {
         [100.0, 0.0, 0.0],
         [0.0, 100.0, 0.0],
     ];
-    let point_layer = StaticLayer::new(point_symbol, points);
+    let point_layer = StaticLayer::from_geometries(point_symbol, points);
 
     let polygon_symbol = PolygonSymbol {
         fill_color: [0.0, 0.5, 0.3, 0.5],
@@ -50,7 +50,7 @@ fn main() {
         ],
         vec![[-30.0, -30.0, 0.0], [30.0, -30.0, 0.0], [0.0, 30.0, 0.0]],
     ];
-    let polygon_layer = StaticLayer::new(polygon_symbol, vec![polygon]);
+    let polygon_layer = StaticLayer::from_geometries(polygon_symbol, vec![polygon]);
 
     let mut runtime = NativeRuntime::new(&|b| b.with_title("Simple yoda map example"));
 
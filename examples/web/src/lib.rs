@@ -31,14 +31,14 @@ pub fn draw_map(canvas: web_sys::HtmlCanvasElement) {
         [200.0, 0.0, 0.0],
         [200.0, 100.0, 0.0],
     ];
-    let line_layer = StaticLayer::new(line_symbol, vec![line]);
+    let line_layer = StaticLayer::from_geometries(line_symbol, vec![line]);
 
     let symbol = CircleSymbol {
         size: 20.0,
         color: [0.0, 0.7, 0.7, 1.0],
         program: None,
     };
-    let layer = StaticLayer::new(
+    let layer = StaticLayer::from_geometries(
         symbol,
         vec![
             [0.0, 0.0, 0.0],
@@ -64,7 +64,7 @@ pub fn draw_map(canvas: web_sys::HtmlCanvasElement) {
         ],
         vec![[-30.0, -30.0, 0.0], [30.0, -30.0, 0.0], [0.0, 30.0, 0.0]],
     ];
-    let polygon_layer = StaticLayer::new(polygon_symbol, vec![polygon]);
+    let polygon_layer = StaticLayer::from_geometries(polygon_symbol, vec![polygon]);
 
     let mut runtime = yoda::runtime::wasm::WasmRuntime::new(canvas);
     let map = runtime.map_mut();
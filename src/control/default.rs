@@ -1,19 +1,21 @@
-use crate::event::{DragEvent, EventListener, EventState, ZoomEvent};
+use crate::event::{DragEvent, EventListener, EventState, KeyEvent, ZoomEvent};
 use crate::map::Map;
 use std::cell::RefCell;
 use std::rc::Rc;
-use winit::event::MouseButton;
+use winit::event::{MouseButton, VirtualKeyCode};
 
 #[derive(Default)]
 pub struct DefaultMapControl {
     map: Option<Rc<RefCell<Map>>>,
     handlers: HandlerIds,
+    history_keys: bool,
 }
 
 #[derive(Debug, Default)]
 struct HandlerIds {
     drag: usize,
     zoom: usize,
+    key: Option<usize>,
 }
 
 impl DefaultMapControl {
@@ -21,9 +23,16 @@ impl DefaultMapControl {
         Self {
             map: None,
             handlers: HandlerIds::default(),
+            history_keys: true,
         }
     }
 
+    /// Enables or disables navigation through the view history with `Alt+Left`/`Alt+Right`
+    /// and the browser back/forward keys. Enabled by default. Takes effect on the next `attach`.
+    pub fn set_history_keys(&mut self, enabled: bool) {
+        self.history_keys = enabled;
+    }
+
     pub fn is_attached(&self) -> bool {
         self.map.is_some()
     }
@@ -32,6 +41,9 @@ impl DefaultMapControl {
         let map = map_cell.borrow();
         self.handlers.drag = map.on(Rc::new(handle_drag));
         self.handlers.zoom = map.on(Rc::new(handle_zoom));
+        if self.history_keys {
+            self.handlers.key = Some(map.on(Rc::new(handle_history_keys)));
+        }
 
         drop(map);
         self.map = Some(map_cell);
@@ -41,6 +53,9 @@ impl DefaultMapControl {
         if let Some(map) = &self.map {
             EventListener::<DragEvent>::off(&*map.borrow(), self.handlers.drag);
             EventListener::<ZoomEvent>::off(&*map.borrow(), self.handlers.zoom);
+            if let Some(key) = self.handlers.key {
+                EventListener::<KeyEvent>::off(&*map.borrow(), key);
+            }
             self.handlers = HandlerIds::default();
             self.map = None;
         }
@@ -54,25 +69,68 @@ impl Drop for DefaultMapControl {
 }
 
 fn handle_zoom(e: ZoomEvent, map: &mut Map) -> EventState {
+    // Wheel zooming produces many events in a row, they are recorded as one history step
+    const HISTORY_MERGE_TIME_MS: u128 = 300;
+    let is_continued =
+        map.control_state().last_zoom_time.elapsed().as_millis() < HISTORY_MERGE_TIME_MS;
+
+    map.cancel_animation();
+    map.record_view();
     map.position_mut().zoom(e.delta, e.cursor_position);
+
+    if is_continued {
+        let state = map.position().view_state();
+        map.history_mut().replace_current(state);
+    } else {
+        map.record_view();
+    }
+
     EventState::Final
 }
 
+fn handle_history_keys(e: KeyEvent, map: &mut Map) -> EventState {
+    let moved = match e.key {
+        VirtualKeyCode::Left if e.modifiers.alt() => map.back(),
+        VirtualKeyCode::Right if e.modifiers.alt() => map.forward(),
+        VirtualKeyCode::NavigateBackward => map.back(),
+        VirtualKeyCode::NavigateForward => map.forward(),
+        _ => return EventState::Continue,
+    };
+
+    if moved {
+        EventState::Final
+    } else {
+        EventState::Continue
+    }
+}
+
 fn handle_drag(e: DragEvent, map: &mut Map) -> EventState {
+    map.cancel_animation();
     match e.button {
-        MouseButton::Left => handle_left_button_drag(e.dx, e.dy, map),
+        MouseButton::Left => handle_left_button_drag(e.dx, e.dy, map, e.curr_cursor_position),
         MouseButton::Right => handle_right_button_drag(e.dx, e.dy, map, e.curr_cursor_position),
         MouseButton::Middle => handle_middle_button_drag(e.dx, e.dy, map),
         MouseButton::Other(_) => EventState::Continue,
     }
 }
 
-fn handle_left_button_drag(dx: i32, dy: i32, map: &mut Map) -> EventState {
-    map.position_mut().translate_px(dx, dy);
+fn handle_left_button_drag(
+    dx: i32,
+    dy: i32,
+    map: &mut Map,
+    cursor_position: [i32; 2],
+) -> EventState {
+    let to = [cursor_position[0] as f32, cursor_position[1] as f32];
+    let from = [to[0] - dx as f32, to[1] + dy as f32];
+    map.position_mut().drag_px(from, to);
     EventState::Final
 }
 
 fn handle_middle_button_drag(_: i32, dy: i32, map: &mut Map) -> EventState {
+    if !map.constraints().tilt_enabled {
+        return EventState::Continue;
+    }
+
     const ANGLE_STEP: f32 = 0.005;
     map.position_mut().rotate(dy as f32 * ANGLE_STEP, 0.0);
 
@@ -85,34 +143,13 @@ fn handle_right_button_drag(
     map: &mut Map,
     cursor_position: [i32; 2],
 ) -> EventState {
-    let position = map.position_mut();
-    let center = position.center();
-    let position_on_map = position.get_map_position(&cursor_position);
-    let v1 = [
-        center[0] - position_on_map[0],
-        center[1] - position_on_map[1],
-    ];
-    let dx = dx as f32 * position.resolution();
-    let dy = dy as f32 * position.resolution();
-
-    let v2 = [v1[0] + dx, v1[1] + dy];
-    let v1_len = (v1[0] * v1[0] + v1[1] * v1[1]).sqrt();
-    let v2_len = (v2[0] * v2[0] + v2[1] * v2[1]).sqrt();
-    if v1_len == 0. || v2_len == 0. {
-        return EventState::Final;
-    }
-
-    let prod = (v1[0] * v2[0] + v1[1] * v2[1]) / v1_len / v2_len;
-    if prod > 1. {
-        return EventState::Final;
-    }
-
-    let mut angle = prod.acos();
-    if v1[0] * v2[1] - v1[1] * v2[0] > 0.0 {
-        angle = -angle;
+    if !map.constraints().rotation_enabled {
+        return EventState::Continue;
     }
 
-    position.rotate(0.0, angle);
+    let to = [cursor_position[0] as f32, cursor_position[1] as f32];
+    let from = [to[0] - dx as f32, to[1] + dy as f32];
+    map.position_mut().rotate_px(from, to);
 
     EventState::Final
 }
@@ -2,10 +2,10 @@ mod default;
 pub use default::DefaultMapControl;
 
 use crate::event::{
-    ClickEvent, DragEvent, EventListener, HandlerStore, TypedHandlerStore, ZoomEvent,
+    ClickEvent, DragEvent, EventListener, HandlerStore, KeyEvent, TypedHandlerStore, ZoomEvent,
 };
 use crate::map::Map;
-use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::event::{ElementState, ModifiersState, MouseButton, MouseScrollDelta, WindowEvent};
 
 #[derive(Debug)]
 pub struct MouseState {
@@ -67,6 +67,7 @@ impl MouseState {
 #[derive(Debug)]
 pub struct ControlState {
     mouse_state: MouseState,
+    modifiers: ModifiersState,
     pub map_size: [u32; 2],
     pub last_zoom_time: instant::Instant,
 }
@@ -75,6 +76,7 @@ impl Default for ControlState {
     fn default() -> Self {
         Self {
             mouse_state: MouseState::default(),
+            modifiers: ModifiersState::empty(),
             map_size: [0, 0],
             last_zoom_time: instant::Instant::now(),
         }
@@ -114,11 +116,26 @@ impl<'a> MapEventDispatcher<'a> {
             },
             CursorMoved { position, .. } => self.cursor_moved(position.x as i32, position.y as i32),
             MouseWheel { delta, .. } => self.wheel(*delta),
+            ModifiersChanged(modifiers) => self.map.control_state_mut().modifiers = *modifiers,
+            KeyboardInput {
+                input:
+                    winit::event::KeyboardInput {
+                        virtual_keycode: Some(key),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => self.trigger(KeyEvent {
+                key: *key,
+                modifiers: self.map.control_state().modifiers,
+            }),
             _ => {}
         }
     }
 
     fn mouse_pressed(&mut self, button: MouseButton) {
+        // Remember the view before it is dragged, if it was changed without user navigation
+        self.map.record_view();
         self.map
             .control_state_mut()
             .mouse_state
@@ -137,6 +154,8 @@ impl<'a> MapEventDispatcher<'a> {
                 cursor_position: self.map.control_state().mouse_state.cursor_position,
                 button,
             });
+        } else {
+            self.map.record_view();
         }
 
         self.map
@@ -0,0 +1,309 @@
+use crate::crs::{Mgrs, Utm};
+use crate::Point;
+use std::fmt::{Display, Formatter};
+
+/// The way geographic coordinates are shown to the user.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CoordinateFormat {
+    /// Decimal degrees with the given number of decimal places, e.g. `55.75581, 37.61764`.
+    Decimal { precision: usize },
+    /// Degrees, minutes and seconds with the given number of decimal places of seconds, e.g.
+    /// `55°45'20.9"N 37°37'03.5"E`.
+    Dms { precision: usize },
+    /// UTM grid coordinates in meters, e.g. `37U 413245 6179768`.
+    Utm,
+    /// MGRS grid reference with the given number of digits per axis, e.g. `37U DB 13245 79768`.
+    Mgrs { precision: usize },
+}
+
+impl Default for CoordinateFormat {
+    fn default() -> Self {
+        CoordinateFormat::Decimal { precision: 6 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseCoordinateError {
+    InvalidFormat,
+    InvalidNumber(String),
+    /// Latitude or longitude is out of the valid range, or the grid zone does not exist.
+    OutOfRange,
+}
+
+impl Display for ParseCoordinateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseCoordinateError::InvalidFormat => write!(f, "unknown coordinate format"),
+            ParseCoordinateError::InvalidNumber(value) => write!(f, "invalid number `{}`", value),
+            ParseCoordinateError::OutOfRange => write!(f, "coordinate is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseCoordinateError {}
+
+/// Formats `[lon, lat]` point. Latitude goes first, as it is usual for geographic coordinates.
+/// If the point cannot be shown in the grid formats (near the poles), decimal degrees are used.
+pub fn format_lon_lat(lon_lat: Point, format: CoordinateFormat) -> String {
+    let [lon, lat] = lon_lat;
+    match format {
+        CoordinateFormat::Decimal { precision } => {
+            format!("{:.*}, {:.*}", precision, lat, precision, lon)
+        }
+        CoordinateFormat::Dms { precision } => format!(
+            "{} {}",
+            format_dms(lat, precision, ['N', 'S']),
+            format_dms(lon, precision, ['E', 'W'])
+        ),
+        CoordinateFormat::Utm => match Utm::from_lon_lat(lon_lat) {
+            Some(utm) => utm.to_string(),
+            None => format_lon_lat(lon_lat, CoordinateFormat::default()),
+        },
+        CoordinateFormat::Mgrs { precision } => match Mgrs::from_lon_lat(lon_lat, precision) {
+            Some(mgrs) => mgrs.to_string(),
+            None => format_lon_lat(lon_lat, CoordinateFormat::default()),
+        },
+    }
+}
+
+fn format_dms(value: f64, precision: usize, hemispheres: [char; 2]) -> String {
+    let hemisphere = if value < 0.0 {
+        hemispheres[1]
+    } else {
+        hemispheres[0]
+    };
+
+    // Round the total number of seconds first, so that 59.99" is not shown as 60"
+    let scale = 10f64.powi(precision as i32);
+    let total = (value.abs() * 3600.0 * scale).round() / scale;
+    let degrees = (total / 3600.0).floor();
+    let minutes = ((total - degrees * 3600.0) / 60.0).floor();
+    let seconds = total - degrees * 3600.0 - minutes * 60.0;
+    let width = if precision > 0 { precision + 3 } else { 2 };
+
+    format!(
+        "{}°{:02}'{:0width$.precision$}\"{}",
+        degrees,
+        minutes,
+        seconds,
+        hemisphere,
+        width = width,
+        precision = precision
+    )
+}
+
+/// Parses a coordinate typed by the user and returns it as `[lon, lat]`.
+///
+/// Supported formats are:
+/// * decimal degrees, latitude first: `55.7558, 37.6176` or `55.7558 37.6176`;
+/// * degrees, minutes and seconds with optional hemisphere letters: `55°45'20.9"N 37°37'3.5"E`,
+///   `N55 45 20.9 E37 37 3.5`. If hemisphere letters are given, the order does not matter;
+/// * UTM: `37U 413245 6179768`;
+/// * MGRS: `37UDB1324579768` or `37U DB 13245 79768`.
+pub fn parse_lon_lat(s: &str) -> Result<Point, ParseCoordinateError> {
+    let s = s.trim();
+    if is_grid_reference(s) {
+        // Strings like `5N 10E` look like grid references too, so the angle formats are tried
+        // if parsing as a grid reference fails
+        if let Ok(utm) = s.parse::<Utm>() {
+            return Ok(utm.to_lon_lat());
+        }
+        if let Ok(mgrs) = s.parse::<Mgrs>() {
+            return Ok(mgrs.to_lon_lat());
+        }
+    }
+
+    let tokens = tokenize(s)?;
+    let (first, second) = split_angles(&tokens)?;
+    let first = parse_angle(first)?;
+    let second = parse_angle(second)?;
+
+    let (lat, lon) = match (first.1, second.1) {
+        (Some('E'), _) | (Some('W'), _) | (_, Some('N')) | (_, Some('S')) => (second.0, first.0),
+        _ => (first.0, second.0),
+    };
+
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+        return Err(ParseCoordinateError::OutOfRange);
+    }
+
+    Ok([lon, lat])
+}
+
+/// UTM and MGRS references start with the zone number followed by the band letter.
+fn is_grid_reference(s: &str) -> bool {
+    let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+    (1..=2).contains(&digits)
+        && s[digits..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Hemisphere(char),
+    Separator,
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ParseCoordinateError> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_ascii_digit() || c == '.' || (number.is_empty() && (c == '-' || c == '+')) {
+                    number.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+
+            let value = number
+                .parse()
+                .map_err(|_| ParseCoordinateError::InvalidNumber(number.clone()))?;
+            tokens.push(Token::Number(value));
+        } else {
+            chars.next();
+            match c.to_ascii_uppercase() {
+                'N' | 'S' | 'E' | 'W' => tokens.push(Token::Hemisphere(c.to_ascii_uppercase())),
+                ',' | ';' => tokens.push(Token::Separator),
+                '°' | '\'' | '"' | '′' | '″' | 'º' => {}
+                c if c.is_whitespace() => {}
+                _ => return Err(ParseCoordinateError::InvalidFormat),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Splits the tokens into latitude and longitude parts.
+fn split_angles(tokens: &[Token]) -> Result<(&[Token], &[Token]), ParseCoordinateError> {
+    if let Some(index) = tokens.iter().position(|t| *t == Token::Separator) {
+        return Ok((&tokens[..index], &tokens[index + 1..]));
+    }
+
+    let hemispheres: Vec<usize> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| matches!(t, Token::Hemisphere(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let index = match hemispheres.as_slice() {
+        // Hemisphere letters before the numbers: `N55 45 E37 37`
+        [0, second] => *second,
+        // Hemisphere letters after the numbers: `55 45N 37 37E`
+        [first, _] => first + 1,
+        [] => {
+            let half = tokens.len() / 2;
+            if half * 2 != tokens.len() {
+                return Err(ParseCoordinateError::InvalidFormat);
+            }
+            half
+        }
+        _ => return Err(ParseCoordinateError::InvalidFormat),
+    };
+
+    Ok((&tokens[..index], &tokens[index..]))
+}
+
+/// Returns the angle in degrees and the hemisphere letter, if it is given.
+fn parse_angle(tokens: &[Token]) -> Result<(f64, Option<char>), ParseCoordinateError> {
+    let mut numbers = vec![];
+    let mut hemisphere = None;
+    for token in tokens {
+        match token {
+            Token::Number(value) => numbers.push(*value),
+            Token::Hemisphere(h) if hemisphere.is_none() => hemisphere = Some(*h),
+            _ => return Err(ParseCoordinateError::InvalidFormat),
+        }
+    }
+
+    if numbers.is_empty() || numbers.len() > 3 {
+        return Err(ParseCoordinateError::InvalidFormat);
+    }
+    if numbers[1..].iter().any(|v| *v < 0.0 || *v >= 60.0) {
+        return Err(ParseCoordinateError::OutOfRange);
+    }
+
+    // `-0` degrees with minutes is a negative angle, so the sign is checked instead of the value
+    let sign = if numbers[0].is_sign_negative() || matches!(hemisphere, Some('S') | Some('W')) {
+        -1.0
+    } else {
+        1.0
+    };
+    let value = numbers
+        .iter()
+        .enumerate()
+        .map(|(i, v)| v.abs() / 60f64.powi(i as i32))
+        .sum::<f64>();
+
+    Ok((sign * value, hemisphere))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(expected: Point, actual: Point) {
+        assert!(
+            (expected[0] - actual[0]).abs() < 1e-5 && (expected[1] - actual[1]).abs() < 1e-5,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn formatting() {
+        let point = [37.617_635, 55.755_814];
+        assert_eq!(
+            "55.7558, 37.6176",
+            format_lon_lat(point, CoordinateFormat::Decimal { precision: 4 })
+        );
+        assert_eq!(
+            "55°45'20.9\"N 37°37'03.5\"E",
+            format_lon_lat(point, CoordinateFormat::Dms { precision: 1 })
+        );
+        assert_eq!(
+            "33°30'00\"S 70°00'00\"W",
+            format_lon_lat([-70.0, -33.5], CoordinateFormat::Dms { precision: 0 })
+        );
+    }
+
+    #[test]
+    fn parsing() {
+        let point = [37.617_635, 55.755_814];
+        assert_near(point, parse_lon_lat("55.755814, 37.617635").unwrap());
+        assert_near(point, parse_lon_lat("55.755814 37.617635").unwrap());
+        assert_near(
+            [37.617_639, 55.755_806],
+            parse_lon_lat("55°45'20.9\"N 37°37'03.5\"E").unwrap(),
+        );
+        assert_near(
+            [37.617_639, 55.755_806],
+            parse_lon_lat("E37 37 3.5 N55 45 20.9").unwrap(),
+        );
+        assert_near([-70.0, -33.5], parse_lon_lat("33 30 S, 70 W").unwrap());
+        assert_near(point, parse_lon_lat("37U 413245.19 6179768.09").unwrap());
+        let [lon, lat] = parse_lon_lat("37UDB1324579768").unwrap();
+        assert!((lon - point[0]).abs() < 1e-4 && (lat - point[1]).abs() < 1e-4);
+        assert_near([10.0, 5.0], parse_lon_lat("5N 10E").unwrap());
+        assert_near([-0.125, 51.5], parse_lon_lat("51 30, -0 7 30").unwrap());
+        assert_near([0.125, -0.5], parse_lon_lat("-0 30, 0 7 30").unwrap());
+
+        assert_eq!(
+            Err(ParseCoordinateError::OutOfRange),
+            parse_lon_lat("95, 10")
+        );
+        assert_eq!(
+            Err(ParseCoordinateError::InvalidFormat),
+            parse_lon_lat("55.7 37.6 12")
+        );
+    }
+}
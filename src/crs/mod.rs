@@ -0,0 +1,260 @@
+mod format;
+pub use format::{format_lon_lat, parse_lon_lat, CoordinateFormat, ParseCoordinateError};
+
+mod utm;
+pub use utm::{Mgrs, Utm};
+
+use crate::{BoundingBox, Point3};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+/// Radius of the sphere used by the Web Mercator projection.
+pub const EARTH_RADIUS: f64 = 6_378_137.0;
+
+/// Latitude at which the Web Mercator map becomes square. Points closer to the poles are clamped
+/// to this latitude.
+pub const MAX_MERCATOR_LATITUDE: f64 = 85.051_128_779_806_59;
+
+/// Transformation between a coordinate reference system and WGS84 geographic coordinates
+/// (longitude and latitude in degrees).
+///
+/// Implement this trait to use a coordinate system that is not built in.
+pub trait Projection {
+    /// Unique identifier of the CRS, for example `EPSG:3857`. Systems with the same identifier
+    /// are considered equal, and no transformation is done between them.
+    fn id(&self) -> &str;
+
+    /// Converts the point from WGS84 `[lon, lat, z]` into this CRS.
+    fn project(&self, point: Point3) -> Point3;
+
+    /// Converts the point from this CRS into WGS84 `[lon, lat, z]`.
+    fn unproject(&self, point: Point3) -> Point3;
+
+    /// Distance along the x axis after which the world repeats itself, if the CRS covers the
+    /// whole globe, like the cylindrical projections do.
+    fn world_width(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Geographic coordinates `[longitude, latitude]` in degrees on the WGS84 ellipsoid.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Wgs84;
+
+impl Projection for Wgs84 {
+    fn id(&self) -> &str {
+        "EPSG:4326"
+    }
+
+    fn project(&self, point: Point3) -> Point3 {
+        point
+    }
+
+    fn unproject(&self, point: Point3) -> Point3 {
+        point
+    }
+
+    fn world_width(&self) -> Option<f64> {
+        Some(360.0)
+    }
+}
+
+/// Spherical Mercator projection used by most web maps, with coordinates in meters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebMercator;
+
+impl Projection for WebMercator {
+    fn id(&self) -> &str {
+        "EPSG:3857"
+    }
+
+    fn project(&self, point: Point3) -> Point3 {
+        let lat = point[1]
+            .clamp(-MAX_MERCATOR_LATITUDE, MAX_MERCATOR_LATITUDE)
+            .to_radians();
+        [
+            EARTH_RADIUS * point[0].to_radians(),
+            EARTH_RADIUS * (std::f64::consts::FRAC_PI_4 + lat / 2.0).tan().ln(),
+            point[2],
+        ]
+    }
+
+    fn unproject(&self, point: Point3) -> Point3 {
+        [
+            (point[0] / EARTH_RADIUS).to_degrees(),
+            (2.0 * (point[1] / EARTH_RADIUS).exp().atan() - std::f64::consts::FRAC_PI_2)
+                .to_degrees(),
+            point[2],
+        ]
+    }
+
+    fn world_width(&self) -> Option<f64> {
+        Some(2.0 * std::f64::consts::PI * EARTH_RADIUS)
+    }
+}
+
+/// Coordinate reference system of the map or a layer.
+#[derive(Clone)]
+pub struct Crs {
+    projection: Rc<dyn Projection>,
+}
+
+impl Crs {
+    pub fn new(projection: Rc<dyn Projection>) -> Self {
+        Self { projection }
+    }
+
+    pub fn wgs84() -> Self {
+        Self::new(Rc::new(Wgs84))
+    }
+
+    pub fn web_mercator() -> Self {
+        Self::new(Rc::new(WebMercator))
+    }
+
+    pub fn id(&self) -> &str {
+        self.projection.id()
+    }
+
+    pub fn projection(&self) -> &dyn Projection {
+        &*self.projection
+    }
+
+    pub fn world_width(&self) -> Option<f64> {
+        self.projection.world_width()
+    }
+
+    /// Converts the point from this CRS into the `target` one.
+    pub fn transform(&self, target: &Crs, point: Point3) -> Point3 {
+        if self == target {
+            point
+        } else {
+            target.projection.project(self.projection.unproject(point))
+        }
+    }
+
+    /// Returns the bounding box in the `target` CRS containing the given one. The edges of the
+    /// box are sampled, since they can be curved in the target CRS.
+    pub fn transform_bbox(&self, target: &Crs, bbox: BoundingBox) -> BoundingBox {
+        if self == target {
+            return bbox;
+        }
+
+        const SAMPLES: usize = 8;
+        let mut result = [f64::MAX, f64::MAX, f64::MIN, f64::MIN];
+        for i in 0..=SAMPLES {
+            let k = i as f64 / SAMPLES as f64;
+            let x = bbox[0] + (bbox[2] - bbox[0]) * k;
+            let y = bbox[1] + (bbox[3] - bbox[1]) * k;
+            for point in [
+                [x, bbox[1], 0.0],
+                [x, bbox[3], 0.0],
+                [bbox[0], y, 0.0],
+                [bbox[2], y, 0.0],
+            ] {
+                let [px, py, _] = self.transform(target, point);
+                result = [
+                    result[0].min(px),
+                    result[1].min(py),
+                    result[2].max(px),
+                    result[3].max(py),
+                ];
+            }
+        }
+
+        result
+    }
+}
+
+/// Moves the point by a whole number of world widths so that it is as close as possible to the
+/// previous point of the geometry. This way a line crossing the antimeridian goes across it
+/// instead of going around the whole world.
+pub(crate) fn unwrap_point(
+    point: Point3,
+    previous_x: &mut Option<f64>,
+    world_width: f64,
+) -> Point3 {
+    let x = match *previous_x {
+        Some(previous) => point[0] - ((point[0] - previous) / world_width).round() * world_width,
+        None => point[0],
+    };
+
+    *previous_x = Some(x);
+    [x, point[1], point[2]]
+}
+
+impl Default for Crs {
+    fn default() -> Self {
+        Self::web_mercator()
+    }
+}
+
+impl PartialEq for Crs {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.projection, &other.projection) || self.id() == other.id()
+    }
+}
+
+impl Debug for Crs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Crs({})", self.id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(expected: Point3, actual: Point3, tolerance: f64) {
+        assert!(
+            (0..3).all(|i| (expected[i] - actual[i]).abs() < tolerance),
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn wgs84_to_web_mercator() {
+        let wgs84 = Crs::wgs84();
+        let mercator = Crs::web_mercator();
+        assert_near(
+            [20_037_508.342_789, 0.0, 0.0],
+            wgs84.transform(&mercator, [180.0, 0.0, 0.0]),
+            1e-3,
+        );
+        assert_near(
+            [-20_037_508.342_789, 20_037_508.342_789, 0.0],
+            wgs84.transform(&mercator, [-180.0, MAX_MERCATOR_LATITUDE, 0.0]),
+            1e-3,
+        );
+
+        let point = [37.617_635, 55.755_814, 10.0];
+        assert_near(
+            point,
+            mercator.transform(&wgs84, wgs84.transform(&mercator, point)),
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn line_is_unwrapped() {
+        let mut previous = None;
+        let line: Vec<Point3> = [[170.0, 0.0, 0.0], [-170.0, 1.0, 0.0], [-160.0, 2.0, 0.0]]
+            .iter()
+            .map(|p| unwrap_point(*p, &mut previous, 360.0))
+            .collect();
+        assert_eq!(
+            vec![[170.0, 0.0, 0.0], [190.0, 1.0, 0.0], [200.0, 2.0, 0.0]],
+            line
+        );
+    }
+
+    #[test]
+    fn bbox_transformation() {
+        let bbox = Crs::wgs84().transform_bbox(&Crs::web_mercator(), [-180.0, 0.0, 0.0, 90.0]);
+        assert!((bbox[0] + 20_037_508.342_789).abs() < 1e-3);
+        assert!((bbox[3] - 20_037_508.342_789).abs() < 1e-3);
+        assert!(bbox[1].abs() < 1e-3 && bbox[2].abs() < 1e-3);
+    }
+}
@@ -0,0 +1,405 @@
+use crate::crs::ParseCoordinateError;
+use crate::Point;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+const FLATTENING: f64 = 1.0 / 298.257_223_563;
+const SCALE_FACTOR: f64 = 0.9996;
+const FALSE_EASTING: f64 = 500_000.0;
+const SOUTH_FALSE_NORTHING: f64 = 10_000_000.0;
+
+const MIN_LATITUDE: f64 = -80.0;
+const MAX_LATITUDE: f64 = 84.0;
+const LATITUDE_BANDS: &[u8] = b"CDEFGHJKLMNPQRSTUVWX";
+
+/// Position in the Universal Transverse Mercator grid. Only defined between 80°S and 84°N.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    /// Zone number from 1 to 60.
+    pub zone: u8,
+    /// Latitude band letter from `C` to `X`. Bands from `N` are in the northern hemisphere.
+    pub band: char,
+    pub easting: f64,
+    pub northing: f64,
+}
+
+/// Coefficients of the Krüger series for the WGS84 ellipsoid.
+struct Series {
+    a: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+    n: f64,
+}
+
+fn series() -> Series {
+    let n = FLATTENING / (2.0 - FLATTENING);
+    let (n2, n3) = (n * n, n * n * n);
+    Series {
+        a: SEMI_MAJOR_AXIS / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+        alpha: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+            61.0 * n3 / 240.0,
+        ],
+        beta: [
+            n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+            n2 / 48.0 + n3 / 15.0,
+            17.0 * n3 / 480.0,
+        ],
+        delta: [
+            2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+            7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+            56.0 * n3 / 15.0,
+        ],
+        n,
+    }
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    zone as f64 * 6.0 - 183.0
+}
+
+fn zone_for(lon: f64, lat: f64) -> u8 {
+    let lon = (lon + 180.0).rem_euclid(360.0) - 180.0;
+    let mut zone = ((lon + 180.0) / 6.0).floor() as u8 % 60 + 1;
+
+    // Exceptions for Norway and Svalbard
+    if (56.0..64.0).contains(&lat) && (3.0..12.0).contains(&lon) {
+        zone = 32;
+    }
+    if (72.0..=84.0).contains(&lat) && (0.0..42.0).contains(&lon) {
+        zone = match lon {
+            lon if lon < 9.0 => 31,
+            lon if lon < 21.0 => 33,
+            lon if lon < 33.0 => 35,
+            _ => 37,
+        };
+    }
+
+    zone
+}
+
+fn band_for(lat: f64) -> char {
+    let index = ((lat - MIN_LATITUDE) / 8.0).floor() as usize;
+    LATITUDE_BANDS[index.min(LATITUDE_BANDS.len() - 1)] as char
+}
+
+/// Returns the southern latitude of the band.
+fn band_latitude(band: char) -> Option<f64> {
+    LATITUDE_BANDS
+        .iter()
+        .position(|b| *b as char == band)
+        .map(|index| MIN_LATITUDE + index as f64 * 8.0)
+}
+
+impl Utm {
+    /// Converts geographic coordinates into UTM. Returns `None` if the point is outside of the
+    /// area covered by UTM.
+    pub fn from_lon_lat(lon_lat: Point) -> Option<Self> {
+        let [lon, lat] = lon_lat;
+        if !(MIN_LATITUDE..=MAX_LATITUDE).contains(&lat) || !lon.is_finite() {
+            return None;
+        }
+
+        let zone = zone_for(lon, lat);
+        let (easting, northing) = to_utm(zone, lon, lat);
+        Some(Self {
+            zone,
+            band: band_for(lat),
+            easting,
+            northing,
+        })
+    }
+
+    pub fn to_lon_lat(&self) -> Point {
+        from_utm(self.zone, self.is_north(), self.easting, self.northing)
+    }
+
+    pub fn is_north(&self) -> bool {
+        self.band >= 'N'
+    }
+}
+
+fn to_utm(zone: u8, lon: f64, lat: f64) -> (f64, f64) {
+    let s = series();
+    let phi = lat.to_radians();
+    let lambda = (lon - central_meridian(zone)).to_radians();
+
+    let k = 2.0 * s.n.sqrt() / (1.0 + s.n);
+    let t = (phi.sin().atanh() - k * (k * phi.sin()).atanh()).sinh();
+    let xi = (t / lambda.cos()).atan();
+    let eta = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+
+    let mut e = eta;
+    let mut n = xi;
+    for (j, alpha) in s.alpha.iter().enumerate() {
+        let j2 = 2.0 * (j + 1) as f64;
+        e += alpha * (j2 * xi).cos() * (j2 * eta).sinh();
+        n += alpha * (j2 * xi).sin() * (j2 * eta).cosh();
+    }
+
+    let false_northing = if lat < 0.0 { SOUTH_FALSE_NORTHING } else { 0.0 };
+    (
+        FALSE_EASTING + SCALE_FACTOR * s.a * e,
+        false_northing + SCALE_FACTOR * s.a * n,
+    )
+}
+
+fn from_utm(zone: u8, is_north: bool, easting: f64, northing: f64) -> Point {
+    let s = series();
+    let false_northing = if is_north { 0.0 } else { SOUTH_FALSE_NORTHING };
+    let xi = (northing - false_northing) / (SCALE_FACTOR * s.a);
+    let eta = (easting - FALSE_EASTING) / (SCALE_FACTOR * s.a);
+
+    let mut xi1 = xi;
+    let mut eta1 = eta;
+    for (j, beta) in s.beta.iter().enumerate() {
+        let j2 = 2.0 * (j + 1) as f64;
+        xi1 -= beta * (j2 * xi).sin() * (j2 * eta).cosh();
+        eta1 -= beta * (j2 * xi).cos() * (j2 * eta).sinh();
+    }
+
+    let chi = (xi1.sin() / eta1.cosh()).asin();
+    let mut phi = chi;
+    for (j, delta) in s.delta.iter().enumerate() {
+        phi += delta * (2.0 * (j + 1) as f64 * chi).sin();
+    }
+
+    let lambda = (eta1.sinh() / xi1.cos()).atan();
+    [
+        central_meridian(zone) + lambda.to_degrees(),
+        phi.to_degrees(),
+    ]
+}
+
+impl Display for Utm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{} {:.0} {:.0}",
+            self.zone, self.band, self.easting, self.northing
+        )
+    }
+}
+
+/// Splits the grid zone designator like `37U` into the zone number and the band letter.
+fn parse_zone(s: &str) -> Result<(u8, char, &str), ParseCoordinateError> {
+    let digits = s.chars().take_while(|c| c.is_ascii_digit()).count();
+    let zone: u8 = s[..digits]
+        .parse()
+        .map_err(|_| ParseCoordinateError::InvalidFormat)?;
+    let band = s[digits..]
+        .chars()
+        .next()
+        .ok_or(ParseCoordinateError::InvalidFormat)?
+        .to_ascii_uppercase();
+    if !(1..=60).contains(&zone) || band_latitude(band).is_none() {
+        return Err(ParseCoordinateError::OutOfRange);
+    }
+
+    Ok((zone, band, s[digits + 1..].trim_start()))
+}
+
+impl FromStr for Utm {
+    type Err = ParseCoordinateError;
+
+    /// Parses the string in the `37U 412345 6178901` format.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (zone, band, rest) = parse_zone(s.trim())?;
+        let values: Vec<&str> = rest.split_whitespace().collect();
+        if values.len() != 2 {
+            return Err(ParseCoordinateError::InvalidFormat);
+        }
+
+        let parse = |value: &str| {
+            value
+                .parse::<f64>()
+                .map_err(|_| ParseCoordinateError::InvalidNumber(value.to_string()))
+        };
+
+        Ok(Self {
+            zone,
+            band,
+            easting: parse(values[0])?,
+            northing: parse(values[1])?,
+        })
+    }
+}
+
+/// Position in the Military Grid Reference System, which is based on UTM, but uses letters for
+/// the 100 km grid squares.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Mgrs {
+    pub utm: Utm,
+    /// Number of digits for each of easting and northing, from 0 (100 km) to 5 (1 m).
+    pub precision: usize,
+}
+
+const COLUMN_LETTERS: [&[u8]; 3] = [b"ABCDEFGH", b"JKLMNPQR", b"STUVWXYZ"];
+const ROW_LETTERS: &[u8] = b"ABCDEFGHJKLMNPQRSTUV";
+const SQUARE_SIZE: f64 = 100_000.0;
+const ROW_CYCLE: f64 = 2_000_000.0;
+
+impl Mgrs {
+    pub fn from_lon_lat(lon_lat: Point, precision: usize) -> Option<Self> {
+        Utm::from_lon_lat(lon_lat).map(|utm| Self {
+            utm,
+            precision: precision.min(5),
+        })
+    }
+
+    pub fn to_lon_lat(&self) -> Point {
+        self.utm.to_lon_lat()
+    }
+
+    fn row_offset(zone: u8) -> usize {
+        // Row letters of even zones are shifted by 5
+        if zone & 1 == 0 {
+            5
+        } else {
+            0
+        }
+    }
+}
+
+impl Display for Mgrs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let utm = &self.utm;
+        let column = (utm.easting / SQUARE_SIZE).floor() as usize;
+        let row = (utm.northing / SQUARE_SIZE).floor() as usize;
+        let column_letter =
+            COLUMN_LETTERS[(utm.zone as usize - 1) % 3][(column.max(1) - 1).min(7)] as char;
+        let row_letter = ROW_LETTERS[(row + Self::row_offset(utm.zone)) % 20] as char;
+
+        write!(
+            f,
+            "{}{} {}{}",
+            utm.zone, utm.band, column_letter, row_letter
+        )?;
+        if self.precision > 0 {
+            let divider = 10f64.powi(5 - self.precision as i32);
+            let easting = (utm.easting % SQUARE_SIZE / divider).floor();
+            let northing = (utm.northing % SQUARE_SIZE / divider).floor();
+            write!(
+                f,
+                " {:0width$} {:0width$}",
+                easting,
+                northing,
+                width = self.precision
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Mgrs {
+    type Err = ParseCoordinateError;
+
+    /// Parses the string in the `37UDB1234567890` format. Spaces between the parts are allowed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (zone, band, rest) = parse_zone(s.trim())?;
+        let rest: String = rest.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut chars = rest.chars();
+        let (column_letter, row_letter) = match (chars.next(), chars.next()) {
+            (Some(c), Some(r)) => (c.to_ascii_uppercase() as u8, r.to_ascii_uppercase() as u8),
+            _ => return Err(ParseCoordinateError::InvalidFormat),
+        };
+
+        let digits: &str = chars.as_str();
+        let precision = digits.len() / 2;
+        if precision * 2 != digits.len()
+            || precision > 5
+            || !digits.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(ParseCoordinateError::InvalidFormat);
+        }
+
+        let multiplier = 10f64.powi(5 - precision as i32);
+        let parse = |value: &str| value.parse::<f64>().unwrap_or(0.0) * multiplier;
+
+        let column = COLUMN_LETTERS[(zone as usize - 1) % 3]
+            .iter()
+            .position(|c| *c == column_letter)
+            .ok_or(ParseCoordinateError::OutOfRange)?;
+        let row = ROW_LETTERS
+            .iter()
+            .position(|r| *r == row_letter)
+            .ok_or(ParseCoordinateError::OutOfRange)?;
+        let row = (row + 20 - Self::row_offset(zone)) % 20;
+
+        let easting = (column + 1) as f64 * SQUARE_SIZE + parse(&digits[..precision]);
+        let northing = row as f64 * SQUARE_SIZE + parse(&digits[precision..]);
+
+        // Row letters repeat every 2000 km, the right cycle is the one that gets into the band
+        let is_north = band >= 'N';
+        let band_center = band_latitude(band).unwrap() + 4.0;
+        let northing = (0..5)
+            .map(|cycle| northing + cycle as f64 * ROW_CYCLE)
+            .min_by(|a, b| {
+                let lat_a = from_utm(zone, is_north, easting, *a)[1];
+                let lat_b = from_utm(zone, is_north, easting, *b)[1];
+                (lat_a - band_center)
+                    .abs()
+                    .partial_cmp(&(lat_b - band_center).abs())
+                    .unwrap()
+            })
+            .unwrap();
+
+        Ok(Self {
+            utm: Utm {
+                zone,
+                band,
+                easting,
+                northing,
+            },
+            precision,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utm_conversion() {
+        let utm = Utm::from_lon_lat([37.617_635, 55.755_814]).unwrap();
+        assert_eq!((37, 'U'), (utm.zone, utm.band));
+        assert!((utm.easting - 413_245.2).abs() < 1.0, "{}", utm.easting);
+        assert!((utm.northing - 6_179_768.1).abs() < 1.0, "{}", utm.northing);
+
+        let [lon, lat] = utm.to_lon_lat();
+        assert!((lon - 37.617_635).abs() < 1e-8 && (lat - 55.755_814).abs() < 1e-8);
+
+        let utm = Utm::from_lon_lat([-70.0, -33.0]).unwrap();
+        assert_eq!((19, 'H'), (utm.zone, utm.band));
+        assert!((utm.northing - 6_348_269.0).abs() < 1.0, "{}", utm.northing);
+        assert_eq!(utm.to_string().parse::<Utm>().unwrap().zone, 19);
+    }
+
+    #[test]
+    fn mgrs_round_trip() {
+        for lon_lat in [
+            [37.617_635, 55.755_814],
+            [-70.0, -33.0],
+            [151.2, -33.86],
+            [0.1, 0.1],
+        ] {
+            let mgrs = Mgrs::from_lon_lat(lon_lat, 5).unwrap();
+            let parsed: Mgrs = mgrs.to_string().parse().unwrap();
+            let [lon, lat] = parsed.to_lon_lat();
+            assert!(
+                (lon - lon_lat[0]).abs() < 1e-4 && (lat - lon_lat[1]).abs() < 1e-4,
+                "{} -> {:?}",
+                mgrs,
+                [lon, lat]
+            );
+        }
+
+        let mgrs = Mgrs::from_lon_lat([37.617_635, 55.755_814], 5).unwrap();
+        assert_eq!("37U DB 13245 79768", mgrs.to_string());
+    }
+}
@@ -1,7 +1,8 @@
-use crate::map::Map;
+use crate::map::{Map, MapPosition};
+use crate::Point;
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
-use winit::event::MouseButton;
+use winit::event::{ModifiersState, MouseButton, VirtualKeyCode};
 
 #[derive(Debug, Clone, Copy)]
 pub struct ClickEvent {
@@ -9,6 +10,13 @@ pub struct ClickEvent {
     pub button: MouseButton,
 }
 
+impl ClickEvent {
+    /// Point of the map ground that was clicked.
+    pub fn map_position(&self, position: &MapPosition) -> Point {
+        position.get_map_position(&self.cursor_position)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct DoubleClickEvent {}
 
@@ -26,6 +34,12 @@ pub struct ZoomEvent {
     pub cursor_position: [i32; 2],
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct KeyEvent {
+    pub key: VirtualKeyCode,
+    pub modifiers: ModifiersState,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EventState {
     Continue,
@@ -39,6 +53,7 @@ pub struct HandlerStore {
     pub double_click: DoubleClickEventStore,
     pub drag: DragEventStore,
     pub zoom: ZoomEventStore,
+    pub key: KeyEventStore,
 }
 
 impl HandlerStore {
@@ -112,6 +127,17 @@ impl TypedHandlerStore<ZoomEvent> for HandlerStore {
     }
 }
 
+type KeyEventStore = Vec<(usize, Rc<dyn Fn(KeyEvent, &mut Map) -> EventState>)>;
+impl TypedHandlerStore<KeyEvent> for HandlerStore {
+    fn get_store(&self) -> &KeyEventStore {
+        &self.key
+    }
+
+    fn get_store_mut(&mut self) -> &mut KeyEventStore {
+        &mut self.key
+    }
+}
+
 pub trait EventListener<E>
 where
     E: Copy,
@@ -0,0 +1,230 @@
+use crate::geometry::Bounds;
+use crate::BoundingBox;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// Identifier of a feature. Data formats use both numeric and string identifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum FeatureId {
+    Number(i64),
+    String(String),
+}
+
+impl Display for FeatureId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FeatureId::Number(id) => write!(f, "{}", id),
+            FeatureId::String(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+impl From<i64> for FeatureId {
+    fn from(id: i64) -> Self {
+        FeatureId::Number(id)
+    }
+}
+
+impl From<&str> for FeatureId {
+    fn from(id: &str) -> Self {
+        FeatureId::String(id.to_owned())
+    }
+}
+
+impl From<String> for FeatureId {
+    fn from(id: String) -> Self {
+        FeatureId::String(id)
+    }
+}
+
+/// Value of a feature attribute.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum AttributeValue {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl AttributeValue {
+    pub fn is_null(&self) -> bool {
+        matches!(self, AttributeValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            AttributeValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            AttributeValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttributeValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Display for AttributeValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeValue::Null => write!(f, "null"),
+            AttributeValue::Bool(value) => write!(f, "{}", value),
+            AttributeValue::Number(value) => write!(f, "{}", value),
+            AttributeValue::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        AttributeValue::Bool(value)
+    }
+}
+
+impl From<f64> for AttributeValue {
+    fn from(value: f64) -> Self {
+        AttributeValue::Number(value)
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        AttributeValue::Number(value as f64)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        AttributeValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        AttributeValue::String(value)
+    }
+}
+
+impl<T: Into<AttributeValue>> From<Option<T>> for AttributeValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(AttributeValue::Null, Into::into)
+    }
+}
+
+/// Attributes of a feature by their names.
+pub type Attributes = BTreeMap<String, AttributeValue>;
+
+/// Geometry together with its identifier and attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature<G> {
+    id: FeatureId,
+    geometry: G,
+    attributes: Attributes,
+}
+
+impl<G> Feature<G> {
+    pub fn new(id: impl Into<FeatureId>, geometry: G) -> Self {
+        Self {
+            id: id.into(),
+            geometry,
+            attributes: Attributes::new(),
+        }
+    }
+
+    pub fn with_attribute(mut self, name: &str, value: impl Into<AttributeValue>) -> Self {
+        self.set_attribute(name, value);
+        self
+    }
+
+    pub fn with_attributes(mut self, attributes: Attributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Creates features with numeric ids equal to the indices of the geometries.
+    pub fn from_geometries(geometries: Vec<G>) -> Vec<Self> {
+        geometries
+            .into_iter()
+            .enumerate()
+            .map(|(index, geometry)| Self::new(index as i64, geometry))
+            .collect()
+    }
+
+    pub fn id(&self) -> &FeatureId {
+        &self.id
+    }
+
+    pub fn geometry(&self) -> &G {
+        &self.geometry
+    }
+
+    pub fn geometry_mut(&mut self) -> &mut G {
+        &mut self.geometry
+    }
+
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Attributes {
+        &mut self.attributes
+    }
+
+    /// Returns the attribute value, or `None` if the feature does not have such attribute.
+    pub fn attribute(&self, name: &str) -> Option<&AttributeValue> {
+        self.attributes.get(name)
+    }
+
+    pub fn set_attribute(&mut self, name: &str, value: impl Into<AttributeValue>) {
+        self.attributes.insert(name.to_owned(), value.into());
+    }
+
+    pub fn into_geometry(self) -> G {
+        self.geometry
+    }
+}
+
+impl<G: Bounds> Bounds for Feature<G> {
+    fn bbox(&self) -> Option<BoundingBox> {
+        self.geometry.bbox()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes() {
+        let feature = Feature::new("pipe-1", vec![[0.0, 0.0, 0.0], [1.0, 2.0, 0.0]])
+            .with_attribute("material", "steel")
+            .with_attribute("diameter", 0.3)
+            .with_attribute("active", true)
+            .with_attribute("installed", None::<i64>);
+
+        assert_eq!(&FeatureId::from("pipe-1"), feature.id());
+        assert_eq!(
+            Some("steel"),
+            feature.attribute("material").unwrap().as_str()
+        );
+        assert_eq!(Some(0.3), feature.attribute("diameter").unwrap().as_f64());
+        assert_eq!(Some(true), feature.attribute("active").unwrap().as_bool());
+        assert!(feature.attribute("installed").unwrap().is_null());
+        assert_eq!(None, feature.attribute("length"));
+        assert_eq!(Some([0.0, 0.0, 1.0, 2.0]), feature.bbox());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::crs::unwrap_point;
+use crate::{BoundingBox, Point3, Polygon, Polyline};
+
+/// Geometry that occupies some area on the map.
+pub trait Bounds {
+    /// Returns the bounding box of the geometry, or `None` if the geometry is empty.
+    fn bbox(&self) -> Option<BoundingBox>;
+}
+
+impl Bounds for Point3 {
+    fn bbox(&self) -> Option<BoundingBox> {
+        Some([self[0], self[1], self[0], self[1]])
+    }
+}
+
+impl<T: Bounds> Bounds for [T] {
+    fn bbox(&self) -> Option<BoundingBox> {
+        self.iter()
+            .fold(None, |acc, item| merge_bbox(acc, item.bbox()))
+    }
+}
+
+impl<T: Bounds> Bounds for Vec<T> {
+    fn bbox(&self) -> Option<BoundingBox> {
+        self[..].bbox()
+    }
+}
+
+/// Geometry that can be converted point by point, for example into another CRS. Points are
+/// passed to `f` in the order they are stored, so `f` can depend on the previous points.
+pub trait TransformPoints: Sized {
+    fn transform_points(&self, f: &mut dyn FnMut(Point3) -> Point3) -> Self;
+
+    /// Moves the points by whole world widths so that the lines and rings crossing the
+    /// antimeridian go across it instead of going around the world. Every part of the geometry
+    /// is then moved as a whole next to the first part.
+    fn unwrap_world(&self, world_width: f64) -> Self {
+        self.unwrap_near(&mut None, world_width)
+    }
+
+    /// Unwraps the geometry with its first point moved next to `anchor`. If there is no anchor
+    /// yet, it is set to the first point.
+    #[doc(hidden)]
+    fn unwrap_near(&self, anchor: &mut Option<f64>, world_width: f64) -> Self;
+
+    /// Unwraps a list of parts, each of them next to the `anchor`.
+    #[doc(hidden)]
+    fn unwrap_parts(parts: &[Self], anchor: &mut Option<f64>, world_width: f64) -> Vec<Self> {
+        parts
+            .iter()
+            .map(|part| part.unwrap_near(anchor, world_width))
+            .collect()
+    }
+}
+
+impl TransformPoints for Point3 {
+    fn transform_points(&self, f: &mut dyn FnMut(Point3) -> Point3) -> Self {
+        f(*self)
+    }
+
+    fn unwrap_near(&self, anchor: &mut Option<f64>, world_width: f64) -> Self {
+        let point = unwrap_point(*self, &mut anchor.clone(), world_width);
+        anchor.get_or_insert(point[0]);
+        point
+    }
+
+    /// A list of points is a line or a ring, so every point is unwrapped next to the previous
+    /// one.
+    fn unwrap_parts(points: &[Self], anchor: &mut Option<f64>, world_width: f64) -> Vec<Self> {
+        let mut previous_x = *anchor;
+        let points: Vec<_> = points
+            .iter()
+            .map(|point| unwrap_point(*point, &mut previous_x, world_width))
+            .collect();
+        if let Some(first) = points.first() {
+            anchor.get_or_insert(first[0]);
+        }
+        points
+    }
+}
+
+impl<T: TransformPoints> TransformPoints for Vec<T> {
+    fn transform_points(&self, f: &mut dyn FnMut(Point3) -> Point3) -> Self {
+        self.iter().map(|item| item.transform_points(f)).collect()
+    }
+
+    fn unwrap_near(&self, anchor: &mut Option<f64>, world_width: f64) -> Self {
+        T::unwrap_parts(self, anchor, world_width)
+    }
+}
+
+/// Geometry of any type. Data formats often mix different geometry types in a single
+/// dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Point3),
+    MultiPoint(Vec<Point3>),
+    LineString(Polyline),
+    MultiLineString(Vec<Polyline>),
+    Polygon(Polygon),
+    MultiPolygon(Vec<Polygon>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+impl Geometry {
+    /// All points of the point geometries, including the ones in collections.
+    pub fn points(&self) -> Vec<&Point3> {
+        match self {
+            Geometry::Point(point) => vec![point],
+            Geometry::MultiPoint(points) => points.iter().collect(),
+            Geometry::GeometryCollection(items) => items.iter().flat_map(Self::points).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// All lines of the line geometries, including the ones in collections.
+    pub fn line_strings(&self) -> Vec<&Polyline> {
+        match self {
+            Geometry::LineString(line) => vec![line],
+            Geometry::MultiLineString(lines) => lines.iter().collect(),
+            Geometry::GeometryCollection(items) => {
+                items.iter().flat_map(Self::line_strings).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// All polygons of the polygon geometries, including the ones in collections.
+    pub fn polygons(&self) -> Vec<&Polygon> {
+        match self {
+            Geometry::Polygon(polygon) => vec![polygon],
+            Geometry::MultiPolygon(polygons) => polygons.iter().collect(),
+            Geometry::GeometryCollection(items) => items.iter().flat_map(Self::polygons).collect(),
+            _ => vec![],
+        }
+    }
+}
+
+impl From<Point3> for Geometry {
+    fn from(point: Point3) -> Self {
+        Geometry::Point(point)
+    }
+}
+
+impl Bounds for Geometry {
+    fn bbox(&self) -> Option<BoundingBox> {
+        match self {
+            Geometry::Point(point) => point.bbox(),
+            Geometry::MultiPoint(points) => points.bbox(),
+            Geometry::LineString(line) => line.bbox(),
+            Geometry::MultiLineString(lines) => lines.bbox(),
+            Geometry::Polygon(polygon) => polygon.bbox(),
+            Geometry::MultiPolygon(polygons) => polygons.bbox(),
+            Geometry::GeometryCollection(items) => items.bbox(),
+        }
+    }
+}
+
+impl TransformPoints for Geometry {
+    fn transform_points(&self, f: &mut dyn FnMut(Point3) -> Point3) -> Self {
+        match self {
+            Geometry::Point(point) => Geometry::Point(point.transform_points(f)),
+            Geometry::MultiPoint(points) => Geometry::MultiPoint(points.transform_points(f)),
+            Geometry::LineString(line) => Geometry::LineString(line.transform_points(f)),
+            Geometry::MultiLineString(lines) => {
+                Geometry::MultiLineString(lines.transform_points(f))
+            }
+            Geometry::Polygon(polygon) => Geometry::Polygon(polygon.transform_points(f)),
+            Geometry::MultiPolygon(polygons) => {
+                Geometry::MultiPolygon(polygons.transform_points(f))
+            }
+            Geometry::GeometryCollection(items) => {
+                Geometry::GeometryCollection(items.transform_points(f))
+            }
+        }
+    }
+
+    fn unwrap_near(&self, anchor: &mut Option<f64>, world_width: f64) -> Self {
+        match self {
+            Geometry::Point(point) => Geometry::Point(point.unwrap_near(anchor, world_width)),
+            // Points are not connected, so each one is a part of its own.
+            Geometry::MultiPoint(points) => Geometry::MultiPoint(
+                points
+                    .iter()
+                    .map(|point| point.unwrap_near(anchor, world_width))
+                    .collect(),
+            ),
+            Geometry::LineString(line) => {
+                Geometry::LineString(line.unwrap_near(anchor, world_width))
+            }
+            Geometry::MultiLineString(lines) => {
+                Geometry::MultiLineString(lines.unwrap_near(anchor, world_width))
+            }
+            Geometry::Polygon(polygon) => {
+                Geometry::Polygon(polygon.unwrap_near(anchor, world_width))
+            }
+            Geometry::MultiPolygon(polygons) => {
+                Geometry::MultiPolygon(polygons.unwrap_near(anchor, world_width))
+            }
+            Geometry::GeometryCollection(items) => {
+                Geometry::GeometryCollection(items.unwrap_near(anchor, world_width))
+            }
+        }
+    }
+}
+
+pub fn merge_bbox(a: Option<BoundingBox>, b: Option<BoundingBox>) -> Option<BoundingBox> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some([
+            a[0].min(b[0]),
+            a[1].min(b[1]),
+            a[2].max(b[2]),
+            a[3].max(b[3]),
+        ]),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+pub fn bbox_intersects(a: &BoundingBox, b: &BoundingBox) -> bool {
+    a[0] <= b[2] && b[0] <= a[2] && a[1] <= b[3] && b[1] <= a[3]
+}
+
+pub fn expand_bbox(bbox: &BoundingBox, margin: f64) -> BoundingBox {
+    [
+        bbox[0] - margin,
+        bbox[1] - margin,
+        bbox[2] + margin,
+        bbox[3] + margin,
+    ]
+}
+
+/// Center of the bounding box, used as the origin for the single precision vertex coordinates.
+pub fn bbox_center(bbox: &BoundingBox) -> Point3 {
+    [(bbox[0] + bbox[2]) / 2.0, (bbox[1] + bbox[3]) / 2.0, 0.0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygon_bbox() {
+        let polygon = vec![
+            vec![[0.0, 0.0, 0.0], [10.0, 5.0, 0.0], [-3.0, 7.0, 0.0]],
+            vec![[1.0, 1.0, 0.0], [2.0, 2.0, 0.0]],
+        ];
+        assert_eq!(Some([-3.0, 0.0, 10.0, 7.0]), polygon.bbox());
+    }
+
+    #[test]
+    fn geometry_collection_parts() {
+        let collection = Geometry::GeometryCollection(vec![
+            Geometry::MultiPoint(vec![[0.0, 0.0, 0.0], [1.0, 1.0, 0.0]]),
+            Geometry::LineString(vec![[-2.0, 0.0, 0.0], [0.0, 3.0, 0.0]]),
+            Geometry::GeometryCollection(vec![Geometry::Point([5.0, 5.0, 0.0])]),
+        ]);
+
+        assert_eq!(3, collection.points().len());
+        assert_eq!(1, collection.line_strings().len());
+        assert!(collection.polygons().is_empty());
+        assert_eq!(Some([-2.0, 0.0, 5.0, 5.0]), collection.bbox());
+    }
+
+    #[test]
+    fn multi_point_across_antimeridian() {
+        let points = Geometry::MultiPoint(vec![
+            [170.0, 0.0, 0.0],
+            [-170.0, 1.0, 0.0],
+            [0.0, 2.0, 0.0],
+            [-170.0, 3.0, 0.0],
+        ]);
+        assert_eq!(
+            Geometry::MultiPoint(vec![
+                [170.0, 0.0, 0.0],
+                [190.0, 1.0, 0.0],
+                [0.0, 2.0, 0.0],
+                [190.0, 3.0, 0.0],
+            ]),
+            points.unwrap_world(360.0)
+        );
+        assert_eq!(
+            Some([0.0, 0.0, 190.0, 3.0]),
+            points.unwrap_world(360.0).bbox()
+        );
+    }
+
+    #[test]
+    fn parts_are_moved_next_to_first_part() {
+        let polygon = vec![
+            vec![
+                [170.0, 0.0, 0.0],
+                [-170.0, 0.0, 0.0],
+                [-170.0, 10.0, 0.0],
+                [170.0, 0.0, 0.0],
+            ],
+            vec![
+                [-175.0, 1.0, 0.0],
+                [-172.0, 1.0, 0.0],
+                [-172.0, 3.0, 0.0],
+                [-175.0, 1.0, 0.0],
+            ],
+        ];
+        let unwrapped = polygon.unwrap_world(360.0);
+        assert_eq!(Some([170.0, 0.0, 190.0, 10.0]), unwrapped.bbox());
+        assert_eq!([185.0, 1.0, 0.0], unwrapped[1][0]);
+
+        let lines = Geometry::MultiLineString(vec![
+            vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]],
+            vec![[170.0, 0.0, 0.0], [-170.0, 0.0, 0.0]],
+        ]);
+        assert_eq!(
+            Geometry::MultiLineString(vec![
+                vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]],
+                vec![[170.0, 0.0, 0.0], [190.0, 0.0, 0.0]],
+            ]),
+            lines.unwrap_world(360.0)
+        );
+    }
+
+    #[test]
+    fn empty_bbox() {
+        let line: Vec<Point3> = vec![];
+        assert_eq!(None, line.bbox());
+    }
+}
@@ -72,30 +72,7 @@ impl GlBuffer {
                 glow::STATIC_DRAW,
             );
 
-            let mut offset = 0;
-            for attrib in V::attributes() {
-                if attrib.value_type.is_int() {
-                    gl.vertex_attrib_pointer_i32(
-                        attrib.location,
-                        attrib.size,
-                        attrib.value_type.glow_type(),
-                        vertex_size as i32,
-                        offset,
-                    );
-                } else {
-                    gl.vertex_attrib_pointer_f32(
-                        attrib.location,
-                        attrib.size,
-                        attrib.value_type.glow_type(),
-                        false,
-                        vertex_size as i32,
-                        offset,
-                    );
-                }
-                gl.enable_vertex_attrib_array(attrib.location);
-
-                offset += attrib.size as i32 * attrib.value_type.size();
-            }
+            set_attributes::<V>(gl);
 
             let vertex_buffer = vertex_buffer;
             let mut vertex_count = vertices.len() as u32;
@@ -124,6 +101,125 @@ impl GlBuffer {
             }
         }
     }
+
+    /// Creates an indexed buffer with space for the given number of vertices and indices. The
+    /// contents are set with `update_vertices` and `update_indices`, and `vertex_count` must be
+    /// set to the number of indices to draw.
+    pub fn with_capacity<V: Vertex>(
+        gl: &Context,
+        vertex_capacity: usize,
+        index_capacity: usize,
+    ) -> Self {
+        unsafe {
+            let vertex_array = gl.create_vertex_array().unwrap();
+            gl.bind_vertex_array(Some(vertex_array));
+
+            let vertex_buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vertex_buffer));
+            gl.buffer_data_size(
+                glow::ARRAY_BUFFER,
+                (std::mem::size_of::<V>() * vertex_capacity) as i32,
+                glow::DYNAMIC_DRAW,
+            );
+            set_attributes::<V>(gl);
+
+            let index_buffer = gl.create_buffer().unwrap();
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+            gl.buffer_data_size(
+                glow::ELEMENT_ARRAY_BUFFER,
+                (4 * index_capacity) as i32,
+                glow::DYNAMIC_DRAW,
+            );
+
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+            gl.bind_vertex_array(None);
+
+            Self {
+                vertex_array,
+                vertex_buffer,
+                index_buffer: Some(index_buffer),
+                vertex_count: 0,
+            }
+        }
+    }
+
+    /// Writes the vertices into the buffer starting from the vertex with index `first`.
+    pub fn update_vertices<V: Vertex>(&self, gl: &Context, first: usize, vertices: &[V]) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vertex_buffer));
+            gl.buffer_sub_data_u8_slice(
+                glow::ARRAY_BUFFER,
+                (std::mem::size_of::<V>() * first) as i32,
+                to_bytes(&vertices[0], std::mem::size_of_val(vertices)),
+            );
+            gl.bind_buffer(glow::ARRAY_BUFFER, None);
+        }
+    }
+
+    /// Writes the indices into the index buffer starting from the position `first`.
+    pub fn update_indices(&self, gl: &Context, first: usize, indices: &[u32]) {
+        let index_buffer = match (self.index_buffer, indices.is_empty()) {
+            (Some(index_buffer), false) => index_buffer,
+            _ => return,
+        };
+
+        unsafe {
+            // The element array binding is a part of the vertex array state
+            gl.bind_vertex_array(Some(self.vertex_array));
+            gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+            gl.buffer_sub_data_u8_slice(
+                glow::ELEMENT_ARRAY_BUFFER,
+                (4 * first) as i32,
+                to_bytes(&indices[0], 4 * indices.len()),
+            );
+            gl.bind_vertex_array(None);
+        }
+    }
+
+    /// Deletes the GL objects of the buffer.
+    pub fn delete(&self, gl: &Context) {
+        unsafe {
+            gl.delete_buffer(self.vertex_buffer);
+            if let Some(index_buffer) = self.index_buffer {
+                gl.delete_buffer(index_buffer);
+            }
+
+            gl.delete_vertex_array(self.vertex_array);
+        }
+    }
+}
+
+/// Sets up the attribute pointers of the vertex type for the bound vertex array and buffer.
+unsafe fn set_attributes<V: Vertex>(gl: &Context) {
+    let vertex_size = std::mem::size_of::<V>();
+    let mut offset = 0;
+    for attrib in V::attributes() {
+        if attrib.value_type.is_int() {
+            gl.vertex_attrib_pointer_i32(
+                attrib.location,
+                attrib.size,
+                attrib.value_type.glow_type(),
+                vertex_size as i32,
+                offset,
+            );
+        } else {
+            gl.vertex_attrib_pointer_f32(
+                attrib.location,
+                attrib.size,
+                attrib.value_type.glow_type(),
+                false,
+                vertex_size as i32,
+                offset,
+            );
+        }
+        gl.enable_vertex_attrib_array(attrib.location);
+
+        offset += attrib.size as i32 * attrib.value_type.size();
+    }
 }
 
 unsafe fn to_bytes<T>(p: &T, size: usize) -> &[u8] {
@@ -0,0 +1,379 @@
+//! Reading of point features from CSV and other delimited text files.
+//!
+//! Points are built either from two coordinate columns or from a column with WKT points. All
+//! the other columns are kept as feature attributes. Numbers, `true`/`false` and empty values
+//! are converted into attributes of the corresponding types, everything else is kept as text.
+
+use crate::crs::Crs;
+use crate::feature::{AttributeValue, Attributes, Feature, FeatureId};
+use crate::geometry::Geometry;
+use crate::io::wkt::{parse_wkt, WktError};
+use crate::layer::StaticLayer;
+use crate::symbol::CircleSymbol;
+use crate::Point3;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+/// Names of the columns, compared ignoring case, that are looked for when the coordinate
+/// columns are not set explicitly.
+const X_COLUMNS: &[&str] = &["x", "lon", "lng", "long", "longitude", "easting"];
+const Y_COLUMNS: &[&str] = &["y", "lat", "latitude", "northing"];
+const WKT_COLUMNS: &[&str] = &["wkt", "geometry", "geom", "the_geom"];
+
+/// The way the point coordinates are stored in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoordinateColumns {
+    /// Detect the columns by their names, e.g. `lon` and `lat`, `x` and `y` or `wkt`.
+    Auto,
+    /// Separate columns for X (longitude) and Y (latitude).
+    Xy { x: String, y: String },
+    /// A column with WKT points, e.g. `POINT (37.6 55.7)`.
+    Wkt(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Field delimiter. If not set, tab, semicolon or comma is chosen by the first line.
+    pub delimiter: Option<u8>,
+    /// Whether the first line contains the column names. If not set, the first line is
+    /// considered to be a header if none of its values is a number. Without a header, the
+    /// columns are named `column1`, `column2` and so on.
+    pub has_header: Option<bool>,
+    pub coordinates: CoordinateColumns,
+    /// Column with the feature ids. If not set, the index of the row is used.
+    pub id_column: Option<String>,
+    /// CRS of the coordinates, WGS84 by default, as GPS coordinates are the most common.
+    pub crs: Option<Crs>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            has_header: None,
+            coordinates: CoordinateColumns::Auto,
+            id_column: None,
+            crs: Some(Crs::wgs84()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CsvError {
+    Io(std::io::Error),
+    Csv(::csv::Error),
+    /// The column is not found, or the coordinate columns cannot be detected.
+    MissingColumn(String),
+    /// Coordinate value cannot be parsed. `row` is the line number in the file, starting
+    /// from 1.
+    InvalidCoordinate {
+        row: usize,
+        column: String,
+        value: String,
+    },
+    InvalidWkt {
+        row: usize,
+        error: WktError,
+    },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvError::Io(error) => write!(f, "failed to read CSV: {}", error),
+            CsvError::Csv(error) => write!(f, "invalid CSV: {}", error),
+            CsvError::MissingColumn(name) => write!(f, "column `{}` is not found", name),
+            CsvError::InvalidCoordinate { row, column, value } => write!(
+                f,
+                "line {}: invalid coordinate `{}` in column `{}`",
+                row, value, column
+            ),
+            CsvError::InvalidWkt { row, error } => write!(f, "line {}: {}", row, error),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<std::io::Error> for CsvError {
+    fn from(error: std::io::Error) -> Self {
+        CsvError::Io(error)
+    }
+}
+
+impl From<::csv::Error> for CsvError {
+    fn from(error: ::csv::Error) -> Self {
+        CsvError::Csv(error)
+    }
+}
+
+enum PointSource {
+    Xy(usize, usize),
+    Wkt(usize),
+}
+
+/// Reads point features from a delimited text.
+pub fn read_features(s: &str, options: &CsvOptions) -> Result<Vec<Feature<Point3>>, CsvError> {
+    // Spreadsheet applications start UTF-8 exports with a byte order mark.
+    let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+    let delimiter = options
+        .delimiter
+        .unwrap_or_else(|| detect_delimiter(s.lines().next().unwrap_or_default()));
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(s.as_bytes());
+    let mut records = reader.records().peekable();
+
+    let first = match records.peek() {
+        Some(Ok(record)) => record.clone(),
+        Some(Err(_)) => return Err(records.next().unwrap().unwrap_err().into()),
+        None => return Ok(vec![]),
+    };
+    let has_header = options.has_header.unwrap_or_else(|| {
+        first
+            .iter()
+            .all(|value| parse_number(value, delimiter).is_none())
+    });
+    let columns: Vec<String> = if has_header {
+        records.next();
+        first.iter().map(|name| name.trim().to_owned()).collect()
+    } else {
+        (1..=first.len()).map(|i| format!("column{}", i)).collect()
+    };
+
+    let column_index = |name: &str| {
+        columns
+            .iter()
+            .position(|column| column.eq_ignore_ascii_case(name))
+            .ok_or_else(|| CsvError::MissingColumn(name.to_owned()))
+    };
+    let detect = |names: &[&str]| names.iter().find_map(|name| column_index(name).ok());
+    let source = match &options.coordinates {
+        CoordinateColumns::Xy { x, y } => PointSource::Xy(column_index(x)?, column_index(y)?),
+        CoordinateColumns::Wkt(name) => PointSource::Wkt(column_index(name)?),
+        CoordinateColumns::Auto => match (detect(X_COLUMNS), detect(Y_COLUMNS)) {
+            (Some(x), Some(y)) => PointSource::Xy(x, y),
+            _ => PointSource::Wkt(
+                detect(WKT_COLUMNS).ok_or_else(|| CsvError::MissingColumn("lon/lat".into()))?,
+            ),
+        },
+    };
+    let id_index = match &options.id_column {
+        Some(name) => Some(column_index(name)?),
+        None => None,
+    };
+
+    let mut features = vec![];
+    for (index, record) in records.enumerate() {
+        let record = record?;
+        let row = record
+            .position()
+            .map_or(index + 1, |position| position.line() as usize);
+
+        let coordinate = |column: usize| {
+            let value = record.get(column).unwrap_or_default();
+            parse_number(value, delimiter).ok_or_else(|| CsvError::InvalidCoordinate {
+                row,
+                column: columns[column].clone(),
+                value: value.to_owned(),
+            })
+        };
+        let point = match source {
+            PointSource::Xy(x, y) => [coordinate(x)?, coordinate(y)?, 0.0],
+            PointSource::Wkt(column) => {
+                let value = record.get(column).unwrap_or_default();
+                match parse_wkt(value) {
+                    Ok(Geometry::Point(point)) => point,
+                    Ok(_) => {
+                        return Err(CsvError::InvalidCoordinate {
+                            row,
+                            column: columns[column].clone(),
+                            value: value.to_owned(),
+                        })
+                    }
+                    Err(error) => return Err(CsvError::InvalidWkt { row, error }),
+                }
+            }
+        };
+
+        let id = match id_index.and_then(|i| record.get(i)) {
+            Some(id) => id
+                .trim()
+                .parse::<i64>()
+                .map_or_else(|_| FeatureId::from(id.trim()), FeatureId::Number),
+            None => FeatureId::Number(index as i64),
+        };
+
+        let attributes: Attributes = record
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| {
+                Some(*i) != id_index
+                    && match source {
+                        PointSource::Xy(x, y) => *i != x && *i != y,
+                        PointSource::Wkt(column) => *i != column,
+                    }
+            })
+            .filter_map(|(i, value)| {
+                let name = columns.get(i)?.clone();
+                Some((name, attribute_value(value, delimiter)))
+            })
+            .collect();
+
+        features.push(Feature::new(id, point).with_attributes(attributes));
+    }
+
+    Ok(features)
+}
+
+/// Reads point features from a delimited text file.
+pub fn read_features_from_file(
+    path: impl AsRef<Path>,
+    options: &CsvOptions,
+) -> Result<Vec<Feature<Point3>>, CsvError> {
+    read_features(&std::fs::read_to_string(path)?, options)
+}
+
+/// Creates a point layer from a delimited text.
+pub fn layer_from_str(
+    s: &str,
+    symbol: CircleSymbol,
+    options: &CsvOptions,
+) -> Result<StaticLayer<Point3, CircleSymbol>, CsvError> {
+    let mut layer = StaticLayer::new(symbol, read_features(s, options)?);
+    layer.set_crs(options.crs.clone());
+    Ok(layer)
+}
+
+/// Creates a point layer from a delimited text file.
+pub fn layer_from_file(
+    path: impl AsRef<Path>,
+    symbol: CircleSymbol,
+    options: &CsvOptions,
+) -> Result<StaticLayer<Point3, CircleSymbol>, CsvError> {
+    layer_from_str(&std::fs::read_to_string(path)?, symbol, options)
+}
+
+fn detect_delimiter(line: &str) -> u8 {
+    [b'\t', b';', b',']
+        .iter()
+        .copied()
+        .max_by_key(|d| line.bytes().filter(|b| b == d).count())
+        .filter(|d| line.as_bytes().contains(d))
+        .unwrap_or(b',')
+}
+
+/// Files with semicolon or tab delimiters often use decimal commas.
+fn parse_number(value: &str, delimiter: u8) -> Option<f64> {
+    let value = value.trim();
+    match value.parse() {
+        Ok(number) => Some(number),
+        Err(_) if delimiter != b',' => value.replace(',', ".").parse().ok(),
+        Err(_) => None,
+    }
+}
+
+fn attribute_value(value: &str, delimiter: u8) -> AttributeValue {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        AttributeValue::Null
+    } else if let Some(number) = parse_number(trimmed, delimiter) {
+        AttributeValue::Number(number)
+    } else if trimmed.eq_ignore_ascii_case("true") {
+        AttributeValue::Bool(true)
+    } else if trimmed.eq_ignore_ascii_case("false") {
+        AttributeValue::Bool(false)
+    } else {
+        AttributeValue::String(value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lon_lat_columns() {
+        let csv = "name;Latitude;Longitude;depth;checked\n\
+                   well 1;55,75;37,61;12,5;true\n\
+                   well 2;55.80;37.70;;false\n";
+        let features = read_features(csv, &CsvOptions::default()).unwrap();
+
+        assert_eq!(2, features.len());
+        assert_eq!(&[37.61, 55.75, 0.0], features[0].geometry());
+        assert_eq!(&FeatureId::Number(1), features[1].id());
+        assert_eq!(
+            Some("well 1"),
+            features[0].attribute("name").unwrap().as_str()
+        );
+        assert_eq!(Some(12.5), features[0].attribute("depth").unwrap().as_f64());
+        assert_eq!(
+            Some(true),
+            features[0].attribute("checked").unwrap().as_bool()
+        );
+        assert!(features[1].attribute("depth").unwrap().is_null());
+        assert_eq!(None, features[0].attribute("Latitude"));
+    }
+
+    #[test]
+    fn wkt_column_and_ids() {
+        let csv = "id\twkt\tnote\nA-1\tPOINT (1 2)\tfirst\n7\tPOINT Z (3 4 5)\tsecond\n";
+        let options = CsvOptions {
+            id_column: Some("id".into()),
+            ..CsvOptions::default()
+        };
+        let features = read_features(csv, &options).unwrap();
+
+        assert_eq!(&FeatureId::from("A-1"), features[0].id());
+        assert_eq!(&FeatureId::Number(7), features[1].id());
+        assert_eq!(&[3.0, 4.0, 5.0], features[1].geometry());
+        assert_eq!(1, features[1].attributes().len());
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let csv = "\u{feff}lat;lon;name\n55,75;37,61;well\n";
+        let features = read_features(csv, &CsvOptions::default()).unwrap();
+        assert_eq!(&[37.61, 55.75, 0.0], features[0].geometry());
+
+        let csv = "\u{feff}wkt,name\nPOINT (1 2),a\n";
+        let features = read_features(csv, &CsvOptions::default()).unwrap();
+        assert_eq!(&[1.0, 2.0, 0.0], features[0].geometry());
+    }
+
+    #[test]
+    fn without_header() {
+        let csv = "10,20,a\n30,40,b\n";
+        let options = CsvOptions {
+            coordinates: CoordinateColumns::Xy {
+                x: "column1".into(),
+                y: "column2".into(),
+            },
+            ..CsvOptions::default()
+        };
+        let features = read_features(csv, &options).unwrap();
+
+        assert_eq!(2, features.len());
+        assert_eq!(&[30.0, 40.0, 0.0], features[1].geometry());
+        assert_eq!(
+            Some("b"),
+            features[1].attribute("column3").unwrap().as_str()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = read_features("x,y\n1,2\n3,abc\n", &CsvOptions::default()).unwrap_err();
+        assert_eq!(
+            "line 3: invalid coordinate `abc` in column `y`",
+            error.to_string()
+        );
+
+        assert!(matches!(
+            read_features("a,b\n1,2\n", &CsvOptions::default()),
+            Err(CsvError::MissingColumn(_))
+        ));
+    }
+}
@@ -0,0 +1,437 @@
+//! Reading of [GeoJSON](https://datatracker.ietf.org/doc/html/rfc7946) data.
+//!
+//! GeoJSON coordinates are always in WGS84, so the layers created by this module have the
+//! WGS84 CRS set and are reprojected into the map CRS when drawn.
+
+use crate::crs::Crs;
+use crate::feature::{AttributeValue, Attributes, Feature, FeatureId};
+use crate::geometry::Geometry;
+use crate::layer::StaticLayer;
+use crate::symbol::Symbol;
+use crate::{Point3, Polygon, Polyline};
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum GeoJsonError {
+    Io(std::io::Error),
+    /// The data is not a valid JSON.
+    Json(serde_json::Error),
+    /// The JSON is not a valid GeoJSON. `path` points to the invalid value, e.g.
+    /// `features[2].geometry.coordinates[0]`.
+    Invalid {
+        path: String,
+        message: String,
+    },
+}
+
+impl Display for GeoJsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeoJsonError::Io(error) => write!(f, "failed to read GeoJSON: {}", error),
+            GeoJsonError::Json(error) => write!(f, "invalid JSON: {}", error),
+            GeoJsonError::Invalid { path, message } if path.is_empty() => write!(f, "{}", message),
+            GeoJsonError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for GeoJsonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GeoJsonError::Io(error) => Some(error),
+            GeoJsonError::Json(error) => Some(error),
+            GeoJsonError::Invalid { .. } => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for GeoJsonError {
+    fn from(error: std::io::Error) -> Self {
+        GeoJsonError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(error: serde_json::Error) -> Self {
+        GeoJsonError::Json(error)
+    }
+}
+
+/// Reads features from a GeoJSON string. The string can contain a `FeatureCollection`, a single
+/// `Feature` or a geometry object. Features without ids get their index as the id.
+pub fn read_features(s: &str) -> Result<Vec<Feature<Geometry>>, GeoJsonError> {
+    let value: Value = serde_json::from_str(s)?;
+    let object = as_object(&value, "")?;
+
+    match get_str(object, "type", "")? {
+        "FeatureCollection" => {
+            let features = as_array(get(object, "features", "")?, "features")?;
+            features
+                .iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    let path = format!("features[{}]", index);
+                    read_feature(as_object(value, &path)?, index, &path)
+                })
+                .collect()
+        }
+        "Feature" => Ok(vec![read_feature(object, 0, "")?]),
+        _ => Ok(vec![Feature::new(0, read_geometry(object, "")?)]),
+    }
+}
+
+/// Reads features from a GeoJSON file.
+pub fn read_features_from_file(
+    path: impl AsRef<Path>,
+) -> Result<Vec<Feature<Geometry>>, GeoJsonError> {
+    read_features(&std::fs::read_to_string(path)?)
+}
+
+/// Creates a layer with the features of a GeoJSON string drawn with the given symbol.
+pub fn layer_from_str<S: Symbol<Geometry>>(
+    s: &str,
+    symbol: S,
+) -> Result<StaticLayer<Geometry, S>, GeoJsonError> {
+    Ok(StaticLayer::new(symbol, read_features(s)?).with_crs(Crs::wgs84()))
+}
+
+/// Creates a layer with the features of a GeoJSON file drawn with the given symbol.
+pub fn layer_from_file<S: Symbol<Geometry>>(
+    path: impl AsRef<Path>,
+    symbol: S,
+) -> Result<StaticLayer<Geometry, S>, GeoJsonError> {
+    Ok(StaticLayer::new(symbol, read_features_from_file(path)?).with_crs(Crs::wgs84()))
+}
+
+fn read_feature(
+    object: &Map<String, Value>,
+    index: usize,
+    path: &str,
+) -> Result<Feature<Geometry>, GeoJsonError> {
+    let feature_type = get_str(object, "type", path)?;
+    if feature_type != "Feature" {
+        return Err(invalid(
+            &join(path, "type"),
+            format!("expected `Feature`, got `{}`", feature_type),
+        ));
+    }
+
+    let id = match object.get("id") {
+        None | Some(Value::Null) => FeatureId::Number(index as i64),
+        Some(Value::String(id)) => FeatureId::String(id.clone()),
+        Some(Value::Number(id)) => match id.as_i64() {
+            Some(id) => FeatureId::Number(id),
+            None => FeatureId::String(id.to_string()),
+        },
+        Some(_) => {
+            return Err(invalid(
+                &join(path, "id"),
+                "expected a string or a number".into(),
+            ))
+        }
+    };
+
+    // Features without geometry are kept, as their attributes may still be useful
+    let geometry_path = join(path, "geometry");
+    let geometry = match get(object, "geometry", path)? {
+        Value::Null => Geometry::GeometryCollection(vec![]),
+        value => read_geometry(as_object(value, &geometry_path)?, &geometry_path)?,
+    };
+
+    let properties_path = join(path, "properties");
+    let attributes = match object.get("properties") {
+        None | Some(Value::Null) => Attributes::new(),
+        Some(value) => as_object(value, &properties_path)?
+            .iter()
+            .map(|(name, value)| (name.clone(), attribute_value(value)))
+            .collect(),
+    };
+
+    Ok(Feature::new(id, geometry).with_attributes(attributes))
+}
+
+/// Nested arrays and objects are not supported by the attributes, so they are kept as JSON
+/// strings.
+fn attribute_value(value: &Value) -> AttributeValue {
+    match value {
+        Value::Null => AttributeValue::Null,
+        Value::Bool(value) => AttributeValue::Bool(*value),
+        Value::Number(value) => value
+            .as_f64()
+            .map_or(AttributeValue::Null, AttributeValue::Number),
+        Value::String(value) => AttributeValue::String(value.clone()),
+        Value::Array(_) | Value::Object(_) => AttributeValue::String(value.to_string()),
+    }
+}
+
+fn read_geometry(object: &Map<String, Value>, path: &str) -> Result<Geometry, GeoJsonError> {
+    let geometry_type = get_str(object, "type", path)?;
+    if geometry_type == "GeometryCollection" {
+        let geometries_path = join(path, "geometries");
+        let geometries = as_array(get(object, "geometries", path)?, &geometries_path)?;
+        return geometries
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let path = format!("{}[{}]", geometries_path, index);
+                read_geometry(as_object(value, &path)?, &path)
+            })
+            .collect::<Result<_, _>>()
+            .map(Geometry::GeometryCollection);
+    }
+
+    let coordinates_path = join(path, "coordinates");
+    let coordinates = Coordinates {
+        value: get(object, "coordinates", path)?,
+        path: &coordinates_path,
+    };
+
+    Ok(match geometry_type {
+        "Point" => Geometry::Point(read_point(coordinates)?),
+        "MultiPoint" => Geometry::MultiPoint(read_array(coordinates, read_point)?),
+        "LineString" => Geometry::LineString(read_line(coordinates)?),
+        "MultiLineString" => Geometry::MultiLineString(read_array(coordinates, read_line)?),
+        "Polygon" => Geometry::Polygon(read_polygon(coordinates)?),
+        "MultiPolygon" => Geometry::MultiPolygon(read_array(coordinates, read_polygon)?),
+        _ => {
+            return Err(invalid(
+                &join(path, "type"),
+                format!("unknown geometry type `{}`", geometry_type),
+            ))
+        }
+    })
+}
+
+/// JSON value together with its path for error reporting.
+#[derive(Clone, Copy)]
+struct Coordinates<'a> {
+    value: &'a Value,
+    path: &'a str,
+}
+
+fn read_array<T>(
+    coordinates: Coordinates,
+    read_item: fn(Coordinates) -> Result<T, GeoJsonError>,
+) -> Result<Vec<T>, GeoJsonError> {
+    as_array(coordinates.value, coordinates.path)?
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let path = format!("{}[{}]", coordinates.path, index);
+            read_item(Coordinates { value, path: &path })
+        })
+        .collect()
+}
+
+fn read_point(coordinates: Coordinates) -> Result<Point3, GeoJsonError> {
+    let values = read_array(coordinates, |c| {
+        c.value
+            .as_f64()
+            .ok_or_else(|| invalid(c.path, "expected a number".into()))
+    })?;
+
+    match values[..] {
+        [x, y] => Ok([x, y, 0.0]),
+        [x, y, z, ..] => Ok([x, y, z]),
+        _ => Err(invalid(
+            coordinates.path,
+            "position must have at least two coordinates".into(),
+        )),
+    }
+}
+
+fn read_line(coordinates: Coordinates) -> Result<Polyline, GeoJsonError> {
+    read_positions(coordinates, 2, "line")
+}
+
+fn read_ring(coordinates: Coordinates) -> Result<Polyline, GeoJsonError> {
+    read_positions(coordinates, 4, "linear ring")
+}
+
+fn read_positions(
+    coordinates: Coordinates,
+    min_count: usize,
+    name: &str,
+) -> Result<Polyline, GeoJsonError> {
+    let points = read_array(coordinates, read_point)?;
+    if points.len() < min_count {
+        return Err(invalid(
+            coordinates.path,
+            format!("{} must have at least {} positions", name, min_count),
+        ));
+    }
+    Ok(points)
+}
+
+/// The first ring is the outer boundary of the polygon, the rest are holes.
+fn read_polygon(coordinates: Coordinates) -> Result<Polygon, GeoJsonError> {
+    read_array(coordinates, read_ring)
+}
+
+fn invalid(path: &str, message: String) -> GeoJsonError {
+    GeoJsonError::Invalid {
+        path: path.to_owned(),
+        message,
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn get<'a>(
+    object: &'a Map<String, Value>,
+    key: &str,
+    path: &str,
+) -> Result<&'a Value, GeoJsonError> {
+    object
+        .get(key)
+        .ok_or_else(|| invalid(&join(path, key), "missing member".into()))
+}
+
+fn get_str<'a>(
+    object: &'a Map<String, Value>,
+    key: &str,
+    path: &str,
+) -> Result<&'a str, GeoJsonError> {
+    get(object, key, path)?
+        .as_str()
+        .ok_or_else(|| invalid(&join(path, key), "expected a string".into()))
+}
+
+fn as_object<'a>(value: &'a Value, path: &str) -> Result<&'a Map<String, Value>, GeoJsonError> {
+    value
+        .as_object()
+        .ok_or_else(|| invalid(path, "expected an object".into()))
+}
+
+fn as_array<'a>(value: &'a Value, path: &str) -> Result<&'a Vec<Value>, GeoJsonError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(path, "expected an array".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_collection() {
+        let features = read_features(
+            r#"{
+                "type": "FeatureCollection",
+                "features": [
+                    {
+                        "type": "Feature",
+                        "id": "a",
+                        "geometry": {"type": "Point", "coordinates": [37.6, 55.7]},
+                        "properties": {"name": "Moscow", "population": 12.6, "capital": true, "tags": [1, 2]}
+                    },
+                    {
+                        "type": "Feature",
+                        "geometry": {
+                            "type": "Polygon",
+                            "coordinates": [
+                                [[0, 0], [10, 0], [10, 10], [0, 0]],
+                                [[2, 1], [8, 1], [8, 7], [2, 1]]
+                            ]
+                        },
+                        "properties": null
+                    },
+                    {"type": "Feature", "id": 7, "geometry": null, "properties": {"note": null}}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(3, features.len());
+        assert_eq!(&FeatureId::from("a"), features[0].id());
+        assert_eq!(&Geometry::Point([37.6, 55.7, 0.0]), features[0].geometry());
+        assert_eq!(
+            Some("Moscow"),
+            features[0].attribute("name").unwrap().as_str()
+        );
+        assert_eq!(
+            Some(12.6),
+            features[0].attribute("population").unwrap().as_f64()
+        );
+        assert_eq!(
+            Some("[1,2]"),
+            features[0].attribute("tags").unwrap().as_str()
+        );
+
+        assert_eq!(&FeatureId::Number(1), features[1].id());
+        match features[1].geometry() {
+            Geometry::Polygon(polygon) => assert_eq!(2, polygon.len()),
+            geometry => panic!("unexpected geometry {:?}", geometry),
+        }
+
+        assert_eq!(&FeatureId::Number(7), features[2].id());
+        assert!(features[2].attribute("note").unwrap().is_null());
+    }
+
+    #[test]
+    fn geometry_collection() {
+        let features = read_features(
+            r#"{"type": "GeometryCollection", "geometries": [
+                {"type": "MultiPoint", "coordinates": [[1, 2, 3], [4, 5]]},
+                {"type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]]]}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(1, features.len());
+        assert_eq!(2, features[0].geometry().points().len());
+        assert_eq!(1, features[0].geometry().line_strings().len());
+    }
+
+    #[test]
+    fn error_paths() {
+        let error = read_features(
+            r#"{"type": "FeatureCollection", "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[1, 2], [3, "x"]]}}
+            ]}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            "features[1].geometry.coordinates[1][1]: expected a number",
+            error.to_string()
+        );
+
+        let error = read_features(
+            r#"{"type": "Feature", "geometry": {"type": "Circle", "coordinates": []}}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            "geometry.type: unknown geometry type `Circle`",
+            error.to_string()
+        );
+
+        let error = read_features(r#"{"type": "Polygon", "coordinates": [[]]}"#).unwrap_err();
+        assert_eq!(
+            "coordinates[0]: linear ring must have at least 4 positions",
+            error.to_string()
+        );
+
+        let error = read_features(
+            r#"{"type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]], [[0, 0]]]}"#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            "coordinates[1]: line must have at least 2 positions",
+            error.to_string()
+        );
+
+        assert!(matches!(
+            read_features("{").unwrap_err(),
+            GeoJsonError::Json(_)
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+//! Reading of GPX files with waypoints, routes and tracks.
+//!
+//! Point elevations are stored as the Z coordinate. Names, descriptions and other text
+//! properties become feature attributes.
+
+use crate::crs::Crs;
+use crate::feature::{Attributes, Feature};
+use crate::geometry::Geometry;
+use crate::io::xml::{child, child_text, children, invalid, XmlError};
+use crate::layer::{LayerGroup, StaticLayer};
+use crate::symbol::{CircleSymbol, LineSymbol};
+use crate::{Point3, Polyline};
+use roxmltree::{Document, Node};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Text properties of waypoints, routes and tracks that are read as attributes.
+const TEXT_PROPERTIES: &[&str] = &["name", "cmt", "desc", "src", "sym", "type"];
+
+/// Contents of a GPX file. Ids of the features are their indices in the file.
+#[derive(Debug, Clone, Default)]
+pub struct GpxData {
+    /// Waypoints. The time of the waypoint is stored in the `time` attribute.
+    pub waypoints: Vec<Feature<Point3>>,
+    /// Routes. Time of the first and the last points is stored in the `start_time` and
+    /// `end_time` attributes.
+    pub routes: Vec<Feature<Polyline>>,
+    /// Time of each route point, indexed by the route and the point.
+    pub route_times: Vec<Vec<Option<String>>>,
+    /// Tracks as `LineString`s, or `MultiLineString`s for tracks with several segments. Time of
+    /// the first and the last points is stored in the `start_time` and `end_time` attributes.
+    pub tracks: Vec<Feature<Geometry>>,
+    /// Time of each track point, indexed by the track, the segment and the point.
+    pub track_times: Vec<Vec<Vec<Option<String>>>>,
+}
+
+impl GpxData {
+    /// Creates a group of layers with waypoints on top of routes and tracks.
+    pub fn into_layer_group(
+        self,
+        waypoint_symbol: CircleSymbol,
+        route_symbol: LineSymbol,
+        track_symbol: LineSymbol,
+    ) -> LayerGroup {
+        let mut group = LayerGroup::new().with_crs(Crs::wgs84());
+        group.add(Rc::new(RefCell::new(
+            StaticLayer::new(track_symbol, self.tracks).with_crs(Crs::wgs84()),
+        )));
+        group.add(Rc::new(RefCell::new(
+            StaticLayer::new(route_symbol, self.routes).with_crs(Crs::wgs84()),
+        )));
+        group.add(Rc::new(RefCell::new(
+            StaticLayer::new(waypoint_symbol, self.waypoints).with_crs(Crs::wgs84()),
+        )));
+        group
+    }
+}
+
+pub fn read_gpx(s: &str) -> Result<GpxData, XmlError> {
+    let document = Document::parse(s)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "gpx" {
+        return Err(invalid(root, "root element is not `gpx`".into()));
+    }
+
+    let mut data = GpxData::default();
+    for (index, node) in children(root, "wpt").enumerate() {
+        let point = read_point(node)?;
+        let mut attributes = text_attributes(node);
+        if let Some(time) = child_text(node, "time") {
+            attributes.insert("time".into(), time.into());
+        }
+        data.waypoints
+            .push(Feature::new(index as i64, point).with_attributes(attributes));
+    }
+
+    for (index, node) in children(root, "rte").enumerate() {
+        let points = children(node, "rtept").collect::<Vec<_>>();
+        let geometry = points
+            .iter()
+            .map(|point| read_point(*point))
+            .collect::<Result<Polyline, _>>()?;
+        let times = point_times(&points);
+
+        let mut attributes = text_attributes(node);
+        insert_time_range(&mut attributes, times.iter().flatten());
+        data.routes
+            .push(Feature::new(index as i64, geometry).with_attributes(attributes));
+        data.route_times.push(times);
+    }
+
+    for (index, node) in children(root, "trk").enumerate() {
+        let mut segments = vec![];
+        let mut times = vec![];
+        for segment in children(node, "trkseg") {
+            let points = children(segment, "trkpt").collect::<Vec<_>>();
+            segments.push(
+                points
+                    .iter()
+                    .map(|point| read_point(*point))
+                    .collect::<Result<Polyline, _>>()?,
+            );
+            times.push(point_times(&points));
+        }
+
+        let mut attributes = text_attributes(node);
+        insert_time_range(&mut attributes, times.iter().flatten().flatten());
+
+        let geometry = if segments.len() == 1 {
+            Geometry::LineString(segments.remove(0))
+        } else {
+            Geometry::MultiLineString(segments)
+        };
+        data.tracks
+            .push(Feature::new(index as i64, geometry).with_attributes(attributes));
+        data.track_times.push(times);
+    }
+
+    Ok(data)
+}
+
+pub fn read_gpx_from_file(path: impl AsRef<Path>) -> Result<GpxData, XmlError> {
+    read_gpx(&std::fs::read_to_string(path)?)
+}
+
+/// Reads `[lon, lat, elevation]` of a `wpt`, `rtept` or `trkpt` element.
+fn read_point(node: Node) -> Result<Point3, XmlError> {
+    let coordinate = |name: &str| {
+        let value = node
+            .attribute(name)
+            .ok_or_else(|| invalid(node, format!("missing `{}` attribute", name)))?;
+        value
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| invalid(node, format!("invalid {} `{}`", name, value)))
+    };
+
+    let elevation = match child(node, "ele").and_then(|ele| ele.text()) {
+        Some(value) => value
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| invalid(node, format!("invalid elevation `{}`", value)))?,
+        None => 0.0,
+    };
+
+    Ok([coordinate("lon")?, coordinate("lat")?, elevation])
+}
+
+fn point_times(points: &[Node]) -> Vec<Option<String>> {
+    points
+        .iter()
+        .map(|point| child_text(*point, "time").map(str::to_owned))
+        .collect()
+}
+
+/// Sets the `start_time` and `end_time` attributes to the first and the last of the times.
+fn insert_time_range<'a>(attributes: &mut Attributes, mut times: impl Iterator<Item = &'a String>) {
+    if let Some(start) = times.next() {
+        let end = times.last().unwrap_or(start);
+        attributes.insert("start_time".into(), start.as_str().into());
+        attributes.insert("end_time".into(), end.as_str().into());
+    }
+}
+
+fn text_attributes(node: Node) -> Attributes {
+    TEXT_PROPERTIES
+        .iter()
+        .filter_map(|name| Some(((*name).to_owned(), child_text(node, name)?.into())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="55.75" lon="37.61">
+    <ele>150.5</ele>
+    <time>2021-06-01T10:00:00Z</time>
+    <name>Camp</name>
+  </wpt>
+  <rte>
+    <name>Route</name>
+    <rtept lat="55.0" lon="37.0"><time>2021-06-01T12:00:00Z</time></rtept>
+    <rtept lat="55.5" lon="37.5"/>
+    <rtept lat="56.0" lon="38.0"><time>2021-06-01T13:00:00Z</time></rtept>
+  </rte>
+  <trk>
+    <name>Morning walk</name>
+    <trkseg>
+      <trkpt lat="55.1" lon="37.1"><ele>120</ele><time>2021-06-01T08:00:00Z</time></trkpt>
+      <trkpt lat="55.2" lon="37.2"><ele>125</ele><time>2021-06-01T08:05:00Z</time></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="55.3" lon="37.3"><time>2021-06-01T08:30:00Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+    #[test]
+    fn gpx_contents() {
+        let data = read_gpx(GPX).unwrap();
+
+        assert_eq!(1, data.waypoints.len());
+        let waypoint = &data.waypoints[0];
+        assert_eq!(&[37.61, 55.75, 150.5], waypoint.geometry());
+        assert_eq!(Some("Camp"), waypoint.attribute("name").unwrap().as_str());
+        assert_eq!(
+            Some("2021-06-01T10:00:00Z"),
+            waypoint.attribute("time").unwrap().as_str()
+        );
+
+        let route = &data.routes[0];
+        assert_eq!(3, route.geometry().len());
+        assert_eq!(
+            Some("2021-06-01T12:00:00Z"),
+            route.attribute("start_time").unwrap().as_str()
+        );
+        assert_eq!(
+            Some("2021-06-01T13:00:00Z"),
+            route.attribute("end_time").unwrap().as_str()
+        );
+        assert_eq!(
+            vec![
+                Some("2021-06-01T12:00:00Z".to_owned()),
+                None,
+                Some("2021-06-01T13:00:00Z".to_owned())
+            ],
+            data.route_times[0]
+        );
+
+        let track = &data.tracks[0];
+        match track.geometry() {
+            Geometry::MultiLineString(segments) => {
+                assert_eq!(2, segments.len());
+                assert_eq!([37.2, 55.2, 125.0], segments[0][1]);
+            }
+            geometry => panic!("unexpected geometry {:?}", geometry),
+        }
+        assert_eq!(
+            Some("2021-06-01T08:00:00Z"),
+            track.attribute("start_time").unwrap().as_str()
+        );
+        assert_eq!(
+            Some("2021-06-01T08:30:00Z"),
+            track.attribute("end_time").unwrap().as_str()
+        );
+        assert_eq!(
+            Some("2021-06-01T08:05:00Z"),
+            data.track_times[0][0][1].as_deref()
+        );
+    }
+
+    #[test]
+    fn invalid_point() {
+        let error = read_gpx("<gpx>\n<wpt lat=\"x\" lon=\"1\"/></gpx>").unwrap_err();
+        assert_eq!("line 2: invalid lat `x`", error.to_string());
+    }
+}
@@ -0,0 +1,244 @@
+//! Reading of placemarks from KML files.
+//!
+//! `Point`, `LineString`, `LinearRing`, `Polygon` and `MultiGeometry` placemark geometries are
+//! supported. The `name` and `description` of the placemark and its `ExtendedData` values become
+//! feature attributes. Styles are not read.
+
+use crate::crs::Crs;
+use crate::feature::{AttributeValue, Attributes, Feature, FeatureId};
+use crate::geometry::Geometry;
+use crate::io::xml::{child, child_text, children, invalid, XmlError};
+use crate::layer::StaticLayer;
+use crate::symbol::Symbol;
+use crate::Point3;
+use roxmltree::{Document, Node};
+use std::path::Path;
+
+/// Reads all placemarks of the document, including the ones in nested folders. Placemarks
+/// without the `id` attribute get their index as the id.
+pub fn read_kml(s: &str) -> Result<Vec<Feature<Geometry>>, XmlError> {
+    let document = Document::parse(s)?;
+    document
+        .descendants()
+        .filter(|node| node.is_element() && node.tag_name().name() == "Placemark")
+        .enumerate()
+        .map(|(index, node)| read_placemark(node, index))
+        .collect()
+}
+
+pub fn read_kml_from_file(path: impl AsRef<Path>) -> Result<Vec<Feature<Geometry>>, XmlError> {
+    read_kml(&std::fs::read_to_string(path)?)
+}
+
+/// Creates a layer with the placemarks of a KML string drawn with the given symbol. As the
+/// placemarks can have different geometry types, `CompositeSymbol` is usually used.
+pub fn layer_from_str<S: Symbol<Geometry>>(
+    s: &str,
+    symbol: S,
+) -> Result<StaticLayer<Geometry, S>, XmlError> {
+    Ok(StaticLayer::new(symbol, read_kml(s)?).with_crs(Crs::wgs84()))
+}
+
+pub fn layer_from_file<S: Symbol<Geometry>>(
+    path: impl AsRef<Path>,
+    symbol: S,
+) -> Result<StaticLayer<Geometry, S>, XmlError> {
+    layer_from_str(&std::fs::read_to_string(path)?, symbol)
+}
+
+fn read_placemark(node: Node, index: usize) -> Result<Feature<Geometry>, XmlError> {
+    let id = match node.attribute("id") {
+        Some(id) => FeatureId::from(id),
+        None => FeatureId::Number(index as i64),
+    };
+
+    // Placemarks without geometry are kept for their attributes
+    let geometry = match node
+        .children()
+        .find_map(|child| read_geometry(child).transpose())
+    {
+        Some(geometry) => geometry?,
+        None => Geometry::GeometryCollection(vec![]),
+    };
+
+    let mut attributes = Attributes::new();
+    for name in &["name", "description"] {
+        if let Some(value) = child_text(node, name) {
+            attributes.insert((*name).to_owned(), value.into());
+        }
+    }
+
+    if let Some(extended_data) = child(node, "ExtendedData") {
+        for data in children(extended_data, "Data") {
+            if let Some(name) = data.attribute("name") {
+                attributes.insert(name.to_owned(), text_value(child_text(data, "value")));
+            }
+        }
+        for schema_data in children(extended_data, "SchemaData") {
+            for data in children(schema_data, "SimpleData") {
+                if let Some(name) = data.attribute("name") {
+                    attributes.insert(name.to_owned(), text_value(data.text().map(str::trim)));
+                }
+            }
+        }
+    }
+
+    Ok(Feature::new(id, geometry).with_attributes(attributes))
+}
+
+fn text_value(text: Option<&str>) -> AttributeValue {
+    match text {
+        Some(text) if !text.is_empty() => text.into(),
+        _ => AttributeValue::Null,
+    }
+}
+
+/// Returns `None` if the node is not a geometry element.
+fn read_geometry(node: Node) -> Result<Option<Geometry>, XmlError> {
+    if !node.is_element() {
+        return Ok(None);
+    }
+
+    Ok(Some(match node.tag_name().name() {
+        "Point" => match read_coordinates(node)?[..] {
+            [point] => Geometry::Point(point),
+            _ => return Err(invalid(node, "point must have one coordinate".into())),
+        },
+        "LineString" | "LinearRing" => Geometry::LineString(read_coordinates(node)?),
+        "Polygon" => {
+            let mut rings = vec![];
+            for boundary in
+                children(node, "outerBoundaryIs").chain(children(node, "innerBoundaryIs"))
+            {
+                for ring in children(boundary, "LinearRing") {
+                    let points = read_coordinates(ring)?;
+                    if points.len() < 4 {
+                        return Err(invalid(
+                            ring,
+                            "ring must have at least 4 coordinates".into(),
+                        ));
+                    }
+                    rings.push(points);
+                }
+            }
+            Geometry::Polygon(rings)
+        }
+        "MultiGeometry" => Geometry::GeometryCollection(
+            node.children()
+                .filter_map(|child| read_geometry(child).transpose())
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => return Ok(None),
+    }))
+}
+
+/// Reads the `coordinates` child: whitespace separated `lon,lat[,alt]` tuples.
+fn read_coordinates(node: Node) -> Result<Vec<Point3>, XmlError> {
+    let text = child(node, "coordinates")
+        .and_then(|coordinates| coordinates.text())
+        .unwrap_or_default();
+
+    text.split_whitespace()
+        .map(|tuple| {
+            let values = tuple
+                .split(',')
+                .map(|value| value.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| invalid(node, format!("invalid coordinates `{}`", tuple)))?;
+            match values[..] {
+                [lon, lat] => Ok([lon, lat, 0.0]),
+                [lon, lat, alt] => Ok([lon, lat, alt]),
+                _ => Err(invalid(node, format!("invalid coordinates `{}`", tuple))),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+  <Document>
+    <Folder>
+      <Placemark id="camp">
+        <name>Camp</name>
+        <description>Base camp</description>
+        <ExtendedData>
+          <Data name="capacity"><value>12</value></Data>
+        </ExtendedData>
+        <Point><coordinates>37.61,55.75,150</coordinates></Point>
+      </Placemark>
+    </Folder>
+    <Placemark>
+      <name>Path</name>
+      <LineString>
+        <coordinates>
+          37.0,55.0 37.5,55.5
+          38.0,56.0
+        </coordinates>
+      </LineString>
+    </Placemark>
+    <Placemark>
+      <Polygon>
+        <outerBoundaryIs><LinearRing><coordinates>0,0 10,0 10,10 0,0</coordinates></LinearRing></outerBoundaryIs>
+        <innerBoundaryIs><LinearRing><coordinates>2,1 8,1 8,7 2,1</coordinates></LinearRing></innerBoundaryIs>
+      </Polygon>
+    </Placemark>
+  </Document>
+</kml>"#;
+
+    #[test]
+    fn placemarks() {
+        let features = read_kml(KML).unwrap();
+        assert_eq!(3, features.len());
+
+        assert_eq!(&FeatureId::from("camp"), features[0].id());
+        assert_eq!(
+            &Geometry::Point([37.61, 55.75, 150.0]),
+            features[0].geometry()
+        );
+        assert_eq!(
+            Some("Base camp"),
+            features[0].attribute("description").unwrap().as_str()
+        );
+        assert_eq!(
+            Some("12"),
+            features[0].attribute("capacity").unwrap().as_str()
+        );
+
+        assert_eq!(&FeatureId::Number(1), features[1].id());
+        assert_eq!(3, features[1].geometry().line_strings()[0].len());
+
+        match features[2].geometry() {
+            Geometry::Polygon(rings) => assert_eq!(2, rings.len()),
+            geometry => panic!("unexpected geometry {:?}", geometry),
+        }
+    }
+
+    #[test]
+    fn invalid_coordinates() {
+        let error = read_kml(
+            "<kml><Placemark>\n<Point><coordinates>1;2</coordinates></Point></Placemark></kml>",
+        )
+        .unwrap_err();
+        assert_eq!("line 2: invalid coordinates `1;2`", error.to_string());
+    }
+
+    #[test]
+    fn empty_ring() {
+        for coordinates in &["<coordinates/>", "<coordinates>\n  </coordinates>"] {
+            let error = read_kml(&format!(
+                "<kml><Placemark><Polygon><outerBoundaryIs>\n<LinearRing>{}</LinearRing>\
+                 </outerBoundaryIs></Polygon></Placemark></kml>",
+                coordinates
+            ))
+            .unwrap_err();
+            assert_eq!(
+                "line 2: ring must have at least 4 coordinates",
+                error.to_string()
+            );
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Readers and writers of geographic data formats. Formats that need external crates are
+//! enabled by the cargo feature with the same name.
+
+pub mod wkb;
+pub mod wkt;
+
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "geojson")]
+pub mod geojson;
+
+#[cfg(feature = "gpx")]
+pub mod gpx;
+
+#[cfg(feature = "kml")]
+pub mod kml;
+
+#[cfg(any(feature = "gpx", feature = "kml"))]
+pub mod xml;
+
+#[cfg(feature = "shapefile")]
+pub mod shapefile;
@@ -0,0 +1,382 @@
+//! Reading of ESRI Shapefiles. Attributes are read from the `.dbf` file and the CRS is
+//! detected from the `.prj` file next to the `.shp` file, if they exist.
+
+use crate::crs::Crs;
+use crate::feature::{AttributeValue, Attributes, Feature};
+use crate::geometry::Geometry;
+use crate::layer::StaticLayer;
+use crate::symbol::Symbol;
+use crate::{Point3, Polygon};
+use ::shapefile::dbase::{FieldValue, Record};
+use ::shapefile::{PolygonRing, Shape};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum ShapefileError {
+    Io(std::io::Error),
+    Shapefile(::shapefile::Error),
+}
+
+impl Display for ShapefileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapefileError::Io(error) => write!(f, "failed to read shapefile: {}", error),
+            ShapefileError::Shapefile(error) => write!(f, "invalid shapefile: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ShapefileError {}
+
+impl From<std::io::Error> for ShapefileError {
+    fn from(error: std::io::Error) -> Self {
+        ShapefileError::Io(error)
+    }
+}
+
+impl From<::shapefile::Error> for ShapefileError {
+    fn from(error: ::shapefile::Error) -> Self {
+        ShapefileError::Shapefile(error)
+    }
+}
+
+/// Features read from a shapefile.
+pub struct ShapefileData {
+    /// Features with ids equal to the record numbers, starting from 0. Null shapes are read
+    /// as empty geometry collections, so that the ids match the records.
+    pub features: Vec<Feature<Geometry>>,
+    /// CRS from the `.prj` file, if the file exists and the CRS is supported.
+    pub crs: Option<Crs>,
+}
+
+/// Reads features from the `.shp` file and its companion files.
+pub fn read(path: impl AsRef<Path>) -> Result<ShapefileData, ShapefileError> {
+    let path = path.as_ref();
+
+    let features = if path.with_extension("dbf").exists() {
+        let mut reader = ::shapefile::Reader::from_path(path)?;
+        reader
+            .iter_shapes_and_records()
+            .enumerate()
+            .map(|(index, item)| {
+                let (shape, record) = item?;
+                Ok(Feature::new(index as i64, convert_shape(shape))
+                    .with_attributes(convert_record(record)))
+            })
+            .collect::<Result<_, ShapefileError>>()?
+    } else {
+        let shapes = ::shapefile::ShapeReader::from_path(path)?.read()?;
+        let geometries = shapes.into_iter().map(convert_shape).collect();
+        Feature::from_geometries(geometries)
+    };
+
+    let crs = match std::fs::read_to_string(path.with_extension("prj")) {
+        Ok(wkt) => crs_from_prj(&wkt),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+        Err(error) => return Err(error.into()),
+    };
+
+    Ok(ShapefileData { features, crs })
+}
+
+/// Creates a layer with the features of the shapefile drawn with the given symbol. If the CRS
+/// is detected, it is set to the layer, otherwise the data is considered to be in the map CRS.
+pub fn layer_from_file<S: Symbol<Geometry>>(
+    path: impl AsRef<Path>,
+    symbol: S,
+) -> Result<StaticLayer<Geometry, S>, ShapefileError> {
+    let data = read(path)?;
+    let mut layer = StaticLayer::new(symbol, data.features);
+    layer.set_crs(data.crs);
+    Ok(layer)
+}
+
+/// Detects the CRS from the WKT in the `.prj` file. Only the CRS supported by the crate are
+/// recognized.
+pub fn crs_from_prj(wkt: &str) -> Option<Crs> {
+    let wkt = wkt.to_ascii_lowercase();
+    if wkt.starts_with("projcs") {
+        let is_web_mercator = [
+            "mercator_auxiliary_sphere",
+            "popular visualisation",
+            "pseudo_mercator",
+            "pseudo-mercator",
+            "web_mercator",
+        ]
+        .iter()
+        .any(|name| wkt.contains(name));
+
+        if is_web_mercator {
+            Some(Crs::web_mercator())
+        } else {
+            None
+        }
+    } else if wkt.starts_with("geogcs") && (wkt.contains("wgs_1984") || wkt.contains("wgs 84")) {
+        Some(Crs::wgs84())
+    } else {
+        None
+    }
+}
+
+fn convert_shape(shape: Shape) -> Geometry {
+    match shape {
+        Shape::NullShape => Geometry::GeometryCollection(vec![]),
+        Shape::Point(p) => Geometry::Point([p.x, p.y, 0.0]),
+        Shape::PointM(p) => Geometry::Point([p.x, p.y, 0.0]),
+        Shape::PointZ(p) => Geometry::Point([p.x, p.y, p.z]),
+        Shape::Multipoint(shape) => {
+            Geometry::MultiPoint(shape.points().iter().map(|p| [p.x, p.y, 0.0]).collect())
+        }
+        Shape::MultipointM(shape) => {
+            Geometry::MultiPoint(shape.points().iter().map(|p| [p.x, p.y, 0.0]).collect())
+        }
+        Shape::MultipointZ(shape) => {
+            Geometry::MultiPoint(shape.points().iter().map(|p| [p.x, p.y, p.z]).collect())
+        }
+        Shape::Polyline(shape) => line_geometry(
+            shape
+                .parts()
+                .iter()
+                .map(|part| part.iter().map(|p| [p.x, p.y, 0.0]).collect())
+                .collect(),
+        ),
+        Shape::PolylineM(shape) => line_geometry(
+            shape
+                .parts()
+                .iter()
+                .map(|part| part.iter().map(|p| [p.x, p.y, 0.0]).collect())
+                .collect(),
+        ),
+        Shape::PolylineZ(shape) => line_geometry(
+            shape
+                .parts()
+                .iter()
+                .map(|part| part.iter().map(|p| [p.x, p.y, p.z]).collect())
+                .collect(),
+        ),
+        Shape::Polygon(shape) => polygon_geometry(
+            shape
+                .rings()
+                .iter()
+                .map(|ring| ring_points(ring, |p| [p.x, p.y, 0.0]))
+                .collect(),
+        ),
+        Shape::PolygonM(shape) => polygon_geometry(
+            shape
+                .rings()
+                .iter()
+                .map(|ring| ring_points(ring, |p| [p.x, p.y, 0.0]))
+                .collect(),
+        ),
+        Shape::PolygonZ(shape) => polygon_geometry(
+            shape
+                .rings()
+                .iter()
+                .map(|ring| ring_points(ring, |p| [p.x, p.y, p.z]))
+                .collect(),
+        ),
+        // Multipatches are 3D surfaces, only their points are shown
+        Shape::Multipatch(shape) => Geometry::MultiPoint(
+            shape
+                .patches()
+                .iter()
+                .flat_map(|patch| patch.points())
+                .map(|p| [p.x, p.y, p.z])
+                .collect(),
+        ),
+    }
+}
+
+fn line_geometry(mut parts: Vec<Vec<Point3>>) -> Geometry {
+    if parts.len() == 1 {
+        Geometry::LineString(parts.remove(0))
+    } else {
+        Geometry::MultiLineString(parts)
+    }
+}
+
+/// Returns the ring points and `true` if the ring is an outer one.
+fn ring_points<P>(ring: &PolygonRing<P>, convert: fn(&P) -> Point3) -> (Vec<Point3>, bool) {
+    match ring {
+        PolygonRing::Outer(points) => (points.iter().map(convert).collect(), true),
+        PolygonRing::Inner(points) => (points.iter().map(convert).collect(), false),
+    }
+}
+
+/// Each outer ring of a shapefile polygon starts a new polygon, and the inner rings following
+/// it are its holes.
+fn polygon_geometry(rings: Vec<(Vec<Point3>, bool)>) -> Geometry {
+    let mut polygons: Vec<Polygon> = vec![];
+    for (ring, is_outer) in rings {
+        match polygons.last_mut() {
+            Some(polygon) if !is_outer => polygon.push(ring),
+            _ => polygons.push(vec![ring]),
+        }
+    }
+
+    if polygons.len() == 1 {
+        Geometry::Polygon(polygons.remove(0))
+    } else {
+        Geometry::MultiPolygon(polygons)
+    }
+}
+
+fn convert_record(record: Record) -> Attributes {
+    HashMap::<String, FieldValue>::from(record)
+        .into_iter()
+        .map(|(name, value)| (name, convert_field(value)))
+        .collect()
+}
+
+fn convert_field(value: FieldValue) -> AttributeValue {
+    match value {
+        FieldValue::Character(value) => value.into(),
+        FieldValue::Memo(value) => value.into(),
+        FieldValue::Numeric(value) => value.into(),
+        FieldValue::Float(value) => value.map(f64::from).into(),
+        FieldValue::Double(value) => value.into(),
+        FieldValue::Currency(value) => value.into(),
+        FieldValue::Integer(value) => AttributeValue::Number(value.into()),
+        FieldValue::Logical(value) => value.into(),
+        FieldValue::Date(value) => value
+            .map(|date| format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day()))
+            .into(),
+        FieldValue::DateTime(value) => {
+            let date = value.date();
+            let time = value.time();
+            format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                date.year(),
+                date.month(),
+                date.day(),
+                time.hours(),
+                time.minutes(),
+                time.seconds()
+            )
+            .into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prj_detection() {
+        assert_eq!(
+            Some(Crs::wgs84()),
+            crs_from_prj(
+                r#"GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]],PRIMEM["Greenwich",0.0],UNIT["Degree",0.0174532925199433]]"#
+            )
+        );
+        assert_eq!(
+            Some(Crs::web_mercator()),
+            crs_from_prj(
+                r#"PROJCS["WGS_1984_Web_Mercator_Auxiliary_Sphere",GEOGCS["GCS_WGS_1984",DATUM["D_WGS_1984",SPHEROID["WGS_1984",6378137.0,298.257223563]]],PROJECTION["Mercator_Auxiliary_Sphere"]]"#
+            )
+        );
+        assert_eq!(
+            None,
+            crs_from_prj(r#"PROJCS["NAD_1983_UTM_Zone_10N",GEOGCS["GCS_North_American_1983"]]"#)
+        );
+    }
+
+    #[test]
+    fn polygon_rings() {
+        let outer = vec![[0.0, 0.0, 0.0], [0.0, 10.0, 0.0], [10.0, 0.0, 0.0]];
+        let hole = vec![[1.0, 1.0, 0.0], [2.0, 1.0, 0.0], [1.0, 2.0, 0.0]];
+        let geometry = polygon_geometry(vec![
+            (outer.clone(), true),
+            (hole.clone(), false),
+            (outer.clone(), true),
+        ]);
+
+        assert_eq!(
+            Geometry::MultiPolygon(vec![vec![outer.clone(), hole], vec![outer]]),
+            geometry
+        );
+    }
+
+    #[test]
+    fn point_file() {
+        let data = read("./examples/data/points_wm.shp").unwrap();
+        assert!(!data.features.is_empty());
+        assert!(data
+            .features
+            .iter()
+            .all(|feature| matches!(feature.geometry(), Geometry::Point(_))));
+    }
+
+    /// Writes the shapes into a `.shp` file in the temporary directory.
+    fn write_file<S: ::shapefile::record::EsriShape>(
+        name: &str,
+        shapes: &[S],
+    ) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("yoda_{}_{}.shp", name, std::process::id()));
+        ::shapefile::ShapeWriter::from_path(&path)
+            .unwrap()
+            .write_shapes(shapes)
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn multipatch_file() {
+        use ::shapefile::{Multipatch, Patch, PointZ, NO_DATA};
+
+        let multipatch = Multipatch::with_parts(vec![
+            Patch::TriangleStrip(vec![
+                PointZ::new(0.0, 0.0, 1.0, NO_DATA),
+                PointZ::new(0.0, 1.0, 2.0, NO_DATA),
+                PointZ::new(1.0, 0.0, 3.0, NO_DATA),
+            ]),
+            Patch::OuterRing(vec![
+                PointZ::new(5.0, 5.0, 4.0, NO_DATA),
+                PointZ::new(5.0, 6.0, 4.0, NO_DATA),
+                PointZ::new(6.0, 6.0, 4.0, NO_DATA),
+            ]),
+        ]);
+        let path = write_file("multipatch", &[multipatch]);
+        let data = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("shx")).unwrap();
+
+        match data.features[0].geometry() {
+            Geometry::MultiPoint(points) => {
+                // The ring is closed by the writer
+                assert_eq!(7, points.len());
+                assert_eq!([0.0, 1.0, 2.0], points[1]);
+                assert_eq!([5.0, 5.0, 4.0], points[6]);
+            }
+            geometry => panic!("unexpected geometry {:?}", geometry),
+        }
+    }
+
+    #[test]
+    fn polygon_z_file() {
+        use ::shapefile::{PointZ, PolygonZ, NO_DATA};
+
+        let ring = vec![
+            PointZ::new(0.0, 0.0, 10.0, NO_DATA),
+            PointZ::new(0.0, 10.0, 20.0, NO_DATA),
+            PointZ::new(10.0, 10.0, 30.0, NO_DATA),
+            PointZ::new(0.0, 0.0, 10.0, NO_DATA),
+        ];
+        let path = write_file("polygon_z", &[PolygonZ::new(PolygonRing::Outer(ring))]);
+        let data = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("shx")).unwrap();
+
+        match data.features[0].geometry() {
+            Geometry::Polygon(rings) => {
+                assert_eq!(1, rings.len());
+                assert!(rings[0].contains(&[0.0, 10.0, 20.0]));
+                assert!(rings[0].contains(&[10.0, 10.0, 30.0]));
+            }
+            geometry => panic!("unexpected geometry {:?}", geometry),
+        }
+    }
+}
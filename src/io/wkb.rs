@@ -0,0 +1,399 @@
+//! Conversion of geometries from and to OGC Well-Known Binary.
+//!
+//! Geometries are written in little endian order with ISO type codes, Z coordinates are
+//! written only if some point of the geometry has non-zero Z. When reading, both ISO and
+//! PostGIS extended (EWKB) type codes are supported, M values are ignored.
+
+use crate::geometry::Geometry;
+use crate::io::wkt::has_z;
+use crate::{Point3, Polygon, Polyline};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WkbError {
+    UnexpectedEnd,
+    InvalidByteOrder(u8),
+    UnknownType(u32),
+    /// A multi geometry of the given type contains a member of another type.
+    InvalidMember(u32),
+    /// The hex string has odd length or contains non-hex characters.
+    InvalidHex,
+}
+
+impl Display for WkbError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WkbError::UnexpectedEnd => write!(f, "unexpected end of WKB"),
+            WkbError::InvalidByteOrder(value) => write!(f, "invalid byte order {}", value),
+            WkbError::UnknownType(code) => write!(f, "unknown geometry type {}", code),
+            WkbError::InvalidMember(code) => {
+                write!(f, "invalid member of geometry type {}", code)
+            }
+            WkbError::InvalidHex => write!(f, "invalid hex string"),
+        }
+    }
+}
+
+impl std::error::Error for WkbError {}
+
+/// Geometry that can be written as WKB.
+pub trait ToWkb {
+    fn to_wkb(&self) -> Vec<u8>;
+
+    /// WKB as a hex string, the way PostGIS shows it.
+    fn to_wkb_hex(&self) -> String {
+        self.to_wkb().iter().map(|b| format!("{:02X}", b)).collect()
+    }
+}
+
+impl ToWkb for Geometry {
+    fn to_wkb(&self) -> Vec<u8> {
+        let mut result = vec![];
+        write_geometry(&mut result, self, has_z(self));
+        result
+    }
+}
+
+impl ToWkb for Point3 {
+    fn to_wkb(&self) -> Vec<u8> {
+        Geometry::Point(*self).to_wkb()
+    }
+}
+
+/// Written as `LineString`.
+impl ToWkb for Polyline {
+    fn to_wkb(&self) -> Vec<u8> {
+        Geometry::LineString(self.clone()).to_wkb()
+    }
+}
+
+impl ToWkb for Polygon {
+    fn to_wkb(&self) -> Vec<u8> {
+        Geometry::Polygon(self.clone()).to_wkb()
+    }
+}
+
+const LITTLE_ENDIAN: u8 = 1;
+const ISO_Z_OFFSET: u32 = 1000;
+const ISO_M_OFFSET: u32 = 2000;
+const ISO_ZM_OFFSET: u32 = 3000;
+const EWKB_Z_FLAG: u32 = 0x8000_0000;
+const EWKB_M_FLAG: u32 = 0x4000_0000;
+const EWKB_SRID_FLAG: u32 = 0x2000_0000;
+
+fn type_code(geometry: &Geometry) -> u32 {
+    match geometry {
+        Geometry::Point(_) => 1,
+        Geometry::LineString(_) => 2,
+        Geometry::Polygon(_) => 3,
+        Geometry::MultiPoint(_) => 4,
+        Geometry::MultiLineString(_) => 5,
+        Geometry::MultiPolygon(_) => 6,
+        Geometry::GeometryCollection(_) => 7,
+    }
+}
+
+fn write_geometry(out: &mut Vec<u8>, geometry: &Geometry, z: bool) {
+    out.push(LITTLE_ENDIAN);
+    let code = type_code(geometry) + if z { ISO_Z_OFFSET } else { 0 };
+    out.extend_from_slice(&code.to_le_bytes());
+
+    match geometry {
+        Geometry::Point(point) => write_point(out, point, z),
+        Geometry::LineString(line) => write_points(out, line, z),
+        Geometry::Polygon(polygon) => write_polygon(out, polygon, z),
+        Geometry::MultiPoint(points) => {
+            write_count(out, points.len());
+            for point in points {
+                write_geometry(out, &Geometry::Point(*point), z);
+            }
+        }
+        Geometry::MultiLineString(lines) => {
+            write_count(out, lines.len());
+            for line in lines {
+                out.push(LITTLE_ENDIAN);
+                out.extend_from_slice(&(2 + if z { ISO_Z_OFFSET } else { 0 }).to_le_bytes());
+                write_points(out, line, z);
+            }
+        }
+        Geometry::MultiPolygon(polygons) => {
+            write_count(out, polygons.len());
+            for polygon in polygons {
+                out.push(LITTLE_ENDIAN);
+                out.extend_from_slice(&(3 + if z { ISO_Z_OFFSET } else { 0 }).to_le_bytes());
+                write_polygon(out, polygon, z);
+            }
+        }
+        Geometry::GeometryCollection(items) => {
+            write_count(out, items.len());
+            for item in items {
+                write_geometry(out, item, z);
+            }
+        }
+    }
+}
+
+fn write_count(out: &mut Vec<u8>, count: usize) {
+    out.extend_from_slice(&(count as u32).to_le_bytes());
+}
+
+fn write_point(out: &mut Vec<u8>, point: &Point3, z: bool) {
+    let dimensions = if z { 3 } else { 2 };
+    for value in &point[..dimensions] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn write_points(out: &mut Vec<u8>, points: &[Point3], z: bool) {
+    write_count(out, points.len());
+    for point in points {
+        write_point(out, point, z);
+    }
+}
+
+fn write_polygon(out: &mut Vec<u8>, polygon: &[Vec<Point3>], z: bool) {
+    write_count(out, polygon.len());
+    for ring in polygon {
+        write_points(out, ring, z);
+    }
+}
+
+/// Parses a WKB geometry. Empty points, written as NaN coordinates, are read as empty
+/// `MultiPoint`s.
+pub fn parse_wkb(bytes: &[u8]) -> Result<Geometry, WkbError> {
+    let mut reader = Reader {
+        bytes,
+        position: 0,
+        little_endian: true,
+    };
+    reader.geometry()
+}
+
+/// Parses a WKB geometry given as a hex string, e.g. from a PostGIS query result.
+pub fn parse_wkb_hex(hex: &str) -> Result<Geometry, WkbError> {
+    let hex = hex.trim();
+    if hex.len() & 1 != 0 || !hex.is_ascii() {
+        return Err(WkbError::InvalidHex);
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| WkbError::InvalidHex))
+        .collect::<Result<Vec<_>, _>>()?;
+    parse_wkb(&bytes)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], WkbError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or(WkbError::UnexpectedEnd)?;
+        self.position += N;
+
+        let mut result = [0; N];
+        result.copy_from_slice(bytes);
+        Ok(result)
+    }
+
+    fn u32(&mut self) -> Result<u32, WkbError> {
+        let bytes = self.take::<4>()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, WkbError> {
+        let bytes = self.take::<8>()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, WkbError> {
+        // Byte order can differ for the nested geometries
+        self.little_endian = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            value => return Err(WkbError::InvalidByteOrder(value)),
+        };
+
+        let code = self.u32()?;
+        if code & EWKB_SRID_FLAG != 0 {
+            self.u32()?;
+        }
+
+        let mut z = code & EWKB_Z_FLAG != 0;
+        let mut m = code & EWKB_M_FLAG != 0;
+        let code = code & !(EWKB_Z_FLAG | EWKB_M_FLAG | EWKB_SRID_FLAG);
+        let base = match code {
+            c if c > ISO_ZM_OFFSET => {
+                z = true;
+                m = true;
+                c - ISO_ZM_OFFSET
+            }
+            c if c > ISO_M_OFFSET => {
+                m = true;
+                c - ISO_M_OFFSET
+            }
+            c if c > ISO_Z_OFFSET => {
+                z = true;
+                c - ISO_Z_OFFSET
+            }
+            c => c,
+        };
+
+        Ok(match base {
+            1 => {
+                let point = self.point(z, m)?;
+                if point[0].is_nan() && point[1].is_nan() {
+                    Geometry::MultiPoint(vec![])
+                } else {
+                    Geometry::Point(point)
+                }
+            }
+            2 => Geometry::LineString(self.points(z, m)?),
+            3 => Geometry::Polygon(self.polygon(z, m)?),
+            4 => Geometry::MultiPoint(
+                self.list(Self::geometry)?
+                    .into_iter()
+                    .filter_map(|geometry| match geometry {
+                        Geometry::Point(point) => Some(Ok(point)),
+                        // Empty point
+                        Geometry::MultiPoint(points) if points.is_empty() => None,
+                        _ => Some(Err(WkbError::InvalidMember(code))),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            5 => Geometry::MultiLineString(
+                self.list(Self::geometry)?
+                    .into_iter()
+                    .map(|geometry| match geometry {
+                        Geometry::LineString(line) => Ok(line),
+                        _ => Err(WkbError::InvalidMember(code)),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            6 => Geometry::MultiPolygon(
+                self.list(Self::geometry)?
+                    .into_iter()
+                    .map(|geometry| match geometry {
+                        Geometry::Polygon(polygon) => Ok(polygon),
+                        _ => Err(WkbError::InvalidMember(code)),
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            7 => Geometry::GeometryCollection(self.list(Self::geometry)?),
+            _ => return Err(WkbError::UnknownType(code)),
+        })
+    }
+
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, WkbError>,
+    ) -> Result<Vec<T>, WkbError> {
+        let count = self.u32()? as usize;
+        // The count is not trusted for the allocation, as the data can be broken
+        let mut items = Vec::with_capacity(count.min(1024));
+        let little_endian = self.little_endian;
+        for _ in 0..count {
+            items.push(item(self)?);
+            self.little_endian = little_endian;
+        }
+        Ok(items)
+    }
+
+    fn point(&mut self, z: bool, m: bool) -> Result<Point3, WkbError> {
+        let x = self.f64()?;
+        let y = self.f64()?;
+        let z = if z { self.f64()? } else { 0.0 };
+        if m {
+            self.f64()?;
+        }
+        Ok([x, y, z])
+    }
+
+    fn points(&mut self, z: bool, m: bool) -> Result<Vec<Point3>, WkbError> {
+        self.list(|reader| reader.point(z, m))
+    }
+
+    fn polygon(&mut self, z: bool, m: bool) -> Result<Polygon, WkbError> {
+        self.list(|reader| reader.points(z, m))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn point() {
+        assert_eq!(
+            "0101000000000000000000F03F0000000000000040",
+            [1.0, 2.0, 0.0].to_wkb_hex()
+        );
+        assert_eq!(
+            Ok(Geometry::Point([1.0, 2.0, 0.0])),
+            parse_wkb_hex("0101000000000000000000F03F0000000000000040")
+        );
+        // Big endian EWKB point with SRID 4326 and Z coordinate
+        assert_eq!(
+            Ok(Geometry::Point([1.0, 2.0, 3.0])),
+            parse_wkb_hex("00A0000001000010E63FF000000000000040000000000000004008000000000000")
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let geometries = vec![
+            Geometry::LineString(vec![[0.0, 0.0, 0.0], [10.0, -5.0, 0.0]]),
+            Geometry::MultiPoint(vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]),
+            Geometry::MultiPolygon(vec![vec![
+                vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+                vec![[0.2, 0.2, 0.0], [0.4, 0.2, 0.0], [0.2, 0.4, 0.0]],
+            ]]),
+            Geometry::GeometryCollection(vec![
+                Geometry::Point([1.0, 2.0, 0.0]),
+                Geometry::MultiLineString(vec![vec![[0.0, 0.0, 0.0], [1.0, 1.0, 0.0]]]),
+            ]),
+        ];
+
+        for geometry in geometries {
+            assert_eq!(Ok(geometry.clone()), parse_wkb(&geometry.to_wkb()));
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(Err(WkbError::UnexpectedEnd), parse_wkb(&[1, 1, 0, 0, 0, 0]));
+        assert_eq!(Err(WkbError::InvalidByteOrder(5)), parse_wkb(&[5]));
+        assert_eq!(Err(WkbError::UnknownType(17)), parse_wkb(&[1, 17, 0, 0, 0]));
+        assert_eq!(Err(WkbError::InvalidHex), parse_wkb_hex("01X"));
+
+        let point = Geometry::Point([1.0, 2.0, 0.0]).to_wkb();
+        for code in 4..=6u8 {
+            let mut multi = vec![1, code, 0, 0, 0, 1, 0, 0, 0];
+            multi.extend_from_slice(&point);
+            let expected = if code == 4 {
+                Ok(Geometry::MultiPoint(vec![[1.0, 2.0, 0.0]]))
+            } else {
+                Err(WkbError::InvalidMember(code as u32))
+            };
+            assert_eq!(expected, parse_wkb(&multi));
+        }
+        let line = Geometry::LineString(vec![[0.0, 0.0, 0.0], [1.0, 1.0, 0.0]]).to_wkb();
+        let mut multi = vec![1, 4, 0, 0, 0, 1, 0, 0, 0];
+        multi.extend_from_slice(&line);
+        assert_eq!(Err(WkbError::InvalidMember(4)), parse_wkb(&multi));
+    }
+}
@@ -0,0 +1,520 @@
+//! Conversion of geometries from and to OGC Well-Known Text, e.g. `POINT Z (1 2 3)`.
+//!
+//! Z coordinates are written only if some point of the geometry has non-zero Z. When reading,
+//! M values are ignored, and the `SRID=...;` prefix of the PostGIS extended WKT is skipped.
+
+use crate::geometry::Geometry;
+use crate::{Point3, Polygon, Polyline};
+use std::fmt::{Display, Formatter, Write};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WktError {
+    UnexpectedEnd,
+    /// Unexpected token at the given byte position.
+    UnexpectedToken {
+        position: usize,
+        token: String,
+    },
+    UnknownType(String),
+    InvalidNumber(String),
+}
+
+impl Display for WktError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WktError::UnexpectedEnd => write!(f, "unexpected end of WKT"),
+            WktError::UnexpectedToken { position, token } => {
+                write!(f, "unexpected `{}` at position {}", token, position)
+            }
+            WktError::UnknownType(name) => write!(f, "unknown geometry type `{}`", name),
+            WktError::InvalidNumber(value) => write!(f, "invalid number `{}`", value),
+        }
+    }
+}
+
+impl std::error::Error for WktError {}
+
+/// Geometry that can be written as WKT.
+pub trait ToWkt {
+    fn to_wkt(&self) -> String;
+}
+
+impl ToWkt for Geometry {
+    fn to_wkt(&self) -> String {
+        let mut result = String::new();
+        write_geometry(&mut result, self, has_z(self));
+        result
+    }
+}
+
+impl ToWkt for Point3 {
+    fn to_wkt(&self) -> String {
+        Geometry::Point(*self).to_wkt()
+    }
+}
+
+/// Written as `LINESTRING`.
+impl ToWkt for Polyline {
+    fn to_wkt(&self) -> String {
+        Geometry::LineString(self.clone()).to_wkt()
+    }
+}
+
+impl ToWkt for Polygon {
+    fn to_wkt(&self) -> String {
+        Geometry::Polygon(self.clone()).to_wkt()
+    }
+}
+
+pub(crate) fn has_z(geometry: &Geometry) -> bool {
+    let any_z = |points: &[Point3]| points.iter().any(|p| p[2] != 0.0);
+    match geometry {
+        Geometry::Point(point) => point[2] != 0.0,
+        Geometry::MultiPoint(points) | Geometry::LineString(points) => any_z(points),
+        Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => {
+            lines.iter().any(|line| any_z(line))
+        }
+        Geometry::MultiPolygon(polygons) => polygons
+            .iter()
+            .any(|polygon| polygon.iter().any(|ring| any_z(ring))),
+        Geometry::GeometryCollection(items) => items.iter().any(has_z),
+    }
+}
+
+fn write_geometry(out: &mut String, geometry: &Geometry, z: bool) {
+    let name = match geometry {
+        Geometry::Point(_) => "POINT",
+        Geometry::MultiPoint(_) => "MULTIPOINT",
+        Geometry::LineString(_) => "LINESTRING",
+        Geometry::MultiLineString(_) => "MULTILINESTRING",
+        Geometry::Polygon(_) => "POLYGON",
+        Geometry::MultiPolygon(_) => "MULTIPOLYGON",
+        Geometry::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+    };
+    out.push_str(name);
+    if z {
+        out.push_str(" Z");
+    }
+
+    let is_empty = match geometry {
+        Geometry::Point(_) => false,
+        Geometry::MultiPoint(points) | Geometry::LineString(points) => points.is_empty(),
+        Geometry::MultiLineString(lines) | Geometry::Polygon(lines) => lines.is_empty(),
+        Geometry::MultiPolygon(polygons) => polygons.is_empty(),
+        Geometry::GeometryCollection(items) => items.is_empty(),
+    };
+    if is_empty {
+        out.push_str(" EMPTY");
+        return;
+    }
+
+    out.push(' ');
+    match geometry {
+        Geometry::Point(point) => write_list(out, &[*point], |out, p| write_point(out, p, z)),
+        Geometry::MultiPoint(points) => write_list(out, points, |out, p| {
+            write_list(out, &[*p], |out, p| write_point(out, p, z))
+        }),
+        Geometry::LineString(line) => write_line(out, line, z),
+        Geometry::MultiLineString(lines) => write_list(out, lines, |out, l| write_line(out, l, z)),
+        Geometry::Polygon(polygon) => write_polygon(out, polygon, z),
+        Geometry::MultiPolygon(polygons) => {
+            write_list(out, polygons, |out, p| write_polygon(out, p, z))
+        }
+        Geometry::GeometryCollection(items) => {
+            write_list(out, items, |out, item| write_geometry(out, item, z))
+        }
+    }
+}
+
+fn write_list<T>(out: &mut String, items: &[T], write_item: impl Fn(&mut String, &T)) {
+    out.push('(');
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            out.push_str(", ");
+        }
+        write_item(out, item);
+    }
+    out.push(')');
+}
+
+fn write_point(out: &mut String, point: &Point3, z: bool) {
+    // Writing to a string cannot fail
+    let _ = write!(out, "{} {}", point[0], point[1]);
+    if z {
+        let _ = write!(out, " {}", point[2]);
+    }
+}
+
+fn write_line(out: &mut String, line: &[Point3], z: bool) {
+    write_list(out, line, |out, p| write_point(out, p, z));
+}
+
+fn write_polygon(out: &mut String, polygon: &[Vec<Point3>], z: bool) {
+    write_list(out, polygon, |out, ring| write_line(out, ring, z));
+}
+
+/// Parses a WKT string. Empty points are read as empty `MultiPoint`s.
+pub fn parse_wkt(s: &str) -> Result<Geometry, WktError> {
+    let mut parser = Parser::new(s);
+    if parser
+        .peek_word()
+        .is_some_and(|w| w.eq_ignore_ascii_case("SRID"))
+    {
+        parser.skip_srid()?;
+    }
+
+    let geometry = parser.geometry()?;
+    match parser.next() {
+        None => Ok(geometry),
+        Some((position, token)) => Err(WktError::UnexpectedToken {
+            position,
+            token: token.to_string(),
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(&'a str),
+    Open,
+    Close,
+    Comma,
+    Other(char),
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(s) | Token::Number(s) => write!(f, "{}", s),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+            Token::Other(c) => write!(f, "{}", c),
+        }
+    }
+}
+
+/// Dimensions of the coordinates given after the geometry type.
+#[derive(Clone, Copy)]
+enum Dimensions {
+    /// No tag, so the number of coordinates defines the dimensions.
+    Unknown,
+    Z,
+    M,
+    Zm,
+}
+
+struct Parser<'a> {
+    s: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Self {
+        Self { s, position: 0 }
+    }
+
+    fn peek(&self) -> Option<(usize, Token<'a>)> {
+        let rest = &self.s[self.position..];
+        let start = self.position + (rest.len() - rest.trim_start().len());
+        let rest = &self.s[start..];
+        let c = rest.chars().next()?;
+
+        let token_length = |f: fn(char) -> bool| rest.find(|c: char| !f(c)).unwrap_or(rest.len());
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            c if c.is_ascii_alphabetic() => {
+                Token::Word(&rest[..token_length(|c| c.is_ascii_alphanumeric() || c == '_')])
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => Token::Number(
+                &rest[..token_length(|c| {
+                    c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')
+                })],
+            ),
+            c => Token::Other(c),
+        };
+
+        Some((start, token))
+    }
+
+    fn next(&mut self) -> Option<(usize, Token<'a>)> {
+        let (position, token) = self.peek()?;
+        self.position = position
+            + match token {
+                Token::Word(s) | Token::Number(s) => s.len(),
+                Token::Other(c) => c.len_utf8(),
+                _ => 1,
+            };
+        Some((position, token))
+    }
+
+    fn peek_word(&self) -> Option<&'a str> {
+        match self.peek() {
+            Some((_, Token::Word(word))) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), WktError> {
+        match self.next() {
+            Some((_, token)) if token == expected => Ok(()),
+            Some((position, token)) => Err(WktError::UnexpectedToken {
+                position,
+                token: token.to_string(),
+            }),
+            None => Err(WktError::UnexpectedEnd),
+        }
+    }
+
+    fn skip_srid(&mut self) -> Result<(), WktError> {
+        self.next();
+        self.expect(Token::Other('='))?;
+        self.number()?;
+        self.expect(Token::Other(';'))
+    }
+
+    fn number(&mut self) -> Result<f64, WktError> {
+        match self.next() {
+            Some((_, Token::Number(s))) => {
+                s.parse().map_err(|_| WktError::InvalidNumber(s.to_owned()))
+            }
+            Some((position, token)) => Err(WktError::UnexpectedToken {
+                position,
+                token: token.to_string(),
+            }),
+            None => Err(WktError::UnexpectedEnd),
+        }
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, WktError> {
+        let name = match self.next() {
+            Some((_, Token::Word(word))) => word.to_ascii_uppercase(),
+            Some((position, token)) => {
+                return Err(WktError::UnexpectedToken {
+                    position,
+                    token: token.to_string(),
+                })
+            }
+            None => return Err(WktError::UnexpectedEnd),
+        };
+
+        // Both `POINT Z` and `POINTZ` forms are used. Names of the geometry types do not end
+        // with these letters, so the suffix is always the dimensions tag.
+        let (name, mut dimensions) = if let Some(name) = name.strip_suffix("ZM") {
+            (name.to_owned(), Dimensions::Zm)
+        } else if let Some(name) = name.strip_suffix('Z') {
+            (name.to_owned(), Dimensions::Z)
+        } else if let Some(name) = name.strip_suffix('M') {
+            (name.to_owned(), Dimensions::M)
+        } else {
+            (name, Dimensions::Unknown)
+        };
+
+        if let Some(tag) = self.peek_word() {
+            let tag_dimensions = match tag.to_ascii_uppercase().as_str() {
+                "Z" => Some(Dimensions::Z),
+                "M" => Some(Dimensions::M),
+                "ZM" => Some(Dimensions::Zm),
+                _ => None,
+            };
+            if let Some(tag_dimensions) = tag_dimensions {
+                dimensions = tag_dimensions;
+                self.next();
+            }
+        }
+
+        let is_empty = self
+            .peek_word()
+            .is_some_and(|w| w.eq_ignore_ascii_case("EMPTY"));
+        if is_empty {
+            self.next();
+        }
+
+        let d = dimensions;
+        Ok(match name.as_str() {
+            "POINT" if is_empty => Geometry::MultiPoint(vec![]),
+            "POINT" => {
+                self.expect(Token::Open)?;
+                let point = self.point(d)?;
+                self.expect(Token::Close)?;
+                Geometry::Point(point)
+            }
+            "MULTIPOINT" => Geometry::MultiPoint(self.list(is_empty, |p| p.multi_point_item(d))?),
+            "LINESTRING" => Geometry::LineString(self.list(is_empty, |p| p.point(d))?),
+            "MULTILINESTRING" => {
+                Geometry::MultiLineString(self.list(is_empty, |p| p.list(false, |p| p.point(d)))?)
+            }
+            "POLYGON" => Geometry::Polygon(self.list(is_empty, |p| p.ring(d))?),
+            "MULTIPOLYGON" => {
+                Geometry::MultiPolygon(self.list(is_empty, |p| p.list(false, |p| p.ring(d)))?)
+            }
+            "GEOMETRYCOLLECTION" => {
+                Geometry::GeometryCollection(self.list(is_empty, Self::geometry)?)
+            }
+            _ => return Err(WktError::UnknownType(name)),
+        })
+    }
+
+    /// Reads a list in parentheses, or returns an empty list if the geometry is empty.
+    fn list<T>(
+        &mut self,
+        is_empty: bool,
+        mut item: impl FnMut(&mut Self) -> Result<T, WktError>,
+    ) -> Result<Vec<T>, WktError> {
+        if is_empty {
+            return Ok(vec![]);
+        }
+
+        self.expect(Token::Open)?;
+        let mut items = vec![item(self)?];
+        loop {
+            match self.next() {
+                Some((_, Token::Comma)) => items.push(item(self)?),
+                Some((_, Token::Close)) => return Ok(items),
+                Some((position, token)) => {
+                    return Err(WktError::UnexpectedToken {
+                        position,
+                        token: token.to_string(),
+                    })
+                }
+                None => return Err(WktError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn ring(&mut self, dimensions: Dimensions) -> Result<Vec<Point3>, WktError> {
+        self.list(false, |p| p.point(dimensions))
+    }
+
+    /// Points of a multipoint can be given with or without parentheses.
+    fn multi_point_item(&mut self, dimensions: Dimensions) -> Result<Point3, WktError> {
+        if matches!(self.peek(), Some((_, Token::Open))) {
+            self.next();
+            let point = self.point(dimensions)?;
+            self.expect(Token::Close)?;
+            Ok(point)
+        } else {
+            self.point(dimensions)
+        }
+    }
+
+    fn point(&mut self, dimensions: Dimensions) -> Result<Point3, WktError> {
+        let mut values = vec![self.number()?, self.number()?];
+        while let Some((_, Token::Number(_))) = self.peek() {
+            values.push(self.number()?);
+        }
+
+        let z = match (dimensions, values.len()) {
+            (Dimensions::M, 3) | (_, 2) => 0.0,
+            (Dimensions::Unknown, 3 | 4) | (Dimensions::Z, 3) | (Dimensions::Zm, 4) => values[2],
+            _ => {
+                return Err(WktError::UnexpectedToken {
+                    position: self.position,
+                    token: values[values.len() - 1].to_string(),
+                })
+            }
+        };
+
+        Ok([values[0], values[1], z])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writing() {
+        assert_eq!("POINT (1 2.5)", [1.0, 2.5, 0.0].to_wkt());
+        assert_eq!("POINT Z (1 2 3)", [1.0, 2.0, 3.0].to_wkt());
+        assert_eq!(
+            "LINESTRING (0 0, 10 -5)",
+            vec![[0.0, 0.0, 0.0], [10.0, -5.0, 0.0]].to_wkt()
+        );
+        assert_eq!(
+            "MULTIPOLYGON (((0 0, 1 0, 0 1, 0 0)), ((5 5, 6 5, 5 6, 5 5)))",
+            Geometry::MultiPolygon(vec![
+                vec![vec![
+                    [0.0, 0.0, 0.0],
+                    [1.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0],
+                    [0.0, 0.0, 0.0]
+                ]],
+                vec![vec![
+                    [5.0, 5.0, 0.0],
+                    [6.0, 5.0, 0.0],
+                    [5.0, 6.0, 0.0],
+                    [5.0, 5.0, 0.0]
+                ]],
+            ])
+            .to_wkt()
+        );
+        assert_eq!(
+            "GEOMETRYCOLLECTION (POINT (1 2), MULTIPOINT EMPTY)",
+            Geometry::GeometryCollection(vec![
+                Geometry::Point([1.0, 2.0, 0.0]),
+                Geometry::MultiPoint(vec![]),
+            ])
+            .to_wkt()
+        );
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            Ok(Geometry::Point([1.0, 2.0, 0.0])),
+            parse_wkt("POINT(1 2)")
+        );
+        assert_eq!(
+            Ok(Geometry::Point([1.0, 2.0, 3.0])),
+            parse_wkt("SRID=4326;point z (1 2 3)")
+        );
+        assert_eq!(
+            Ok(Geometry::Point([1.0, 2.0, 0.0])),
+            parse_wkt("POINTM (1 2 3)")
+        );
+        assert_eq!(
+            Ok(Geometry::MultiPoint(vec![[1.0, 2.0, 0.0], [3.0, 4.0, 0.0]])),
+            parse_wkt("MULTIPOINT (1 2, (3 4))")
+        );
+        assert_eq!(
+            Ok(Geometry::Polygon(vec![
+                vec![[0.0, 0.0, 1.0], [10.0, 0.0, 1.0], [0.0, 10.0, 1.0]],
+                vec![[1.0, 1.0, 1.0], [2.0, 1.0, 1.0], [1.0, 2.0, 1.0]],
+            ])),
+            parse_wkt("POLYGON ZM ((0 0 1 5, 10 0 1 5, 0 10 1 5), (1 1 1 5, 2 1 1 5, 1 2 1 5))")
+        );
+        assert_eq!(
+            Ok(Geometry::LineString(vec![])),
+            parse_wkt("LINESTRING EMPTY")
+        );
+
+        let wkt = "GEOMETRYCOLLECTION (POINT (1 2), MULTILINESTRING ((0 0, 1e3 1), (2 2, 3 3)))";
+        assert_eq!(
+            "GEOMETRYCOLLECTION (POINT (1 2), MULTILINESTRING ((0 0, 1000 1), (2 2, 3 3)))",
+            parse_wkt(wkt).unwrap().to_wkt()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            Err(WktError::UnknownType("CIRCLE".into())),
+            parse_wkt("CIRCLE (1 2)")
+        );
+        assert_eq!(
+            Err(WktError::UnexpectedEnd),
+            parse_wkt("LINESTRING (1 2, 3 4")
+        );
+        assert_eq!(
+            Err(WktError::UnexpectedToken {
+                position: 11,
+                token: "x".into()
+            }),
+            parse_wkt("POINT (1 2 x)")
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Helpers shared by the XML based formats.
+
+use roxmltree::Node;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum XmlError {
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    /// The document is a valid XML, but not a valid document of the format. `line` is the line
+    /// of the invalid element, starting from 1.
+    Invalid {
+        line: u32,
+        message: String,
+    },
+}
+
+impl Display for XmlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            XmlError::Io(error) => write!(f, "failed to read file: {}", error),
+            XmlError::Xml(error) => write!(f, "invalid XML: {}", error),
+            XmlError::Invalid { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+impl From<std::io::Error> for XmlError {
+    fn from(error: std::io::Error) -> Self {
+        XmlError::Io(error)
+    }
+}
+
+impl From<roxmltree::Error> for XmlError {
+    fn from(error: roxmltree::Error) -> Self {
+        XmlError::Xml(error)
+    }
+}
+
+pub(crate) fn invalid(node: Node, message: String) -> XmlError {
+    XmlError::Invalid {
+        line: node.document().text_pos_at(node.range().start).row,
+        message,
+    }
+}
+
+/// Child elements with the given local name. Namespaces are ignored, as files in the wild
+/// often use wrong namespace versions.
+pub(crate) fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+pub(crate) fn child<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> Option<Node<'a, 'input>> {
+    children(node, name).next()
+}
+
+/// Trimmed text of the child element, if it exists and is not empty.
+pub(crate) fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)?
+        .text()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
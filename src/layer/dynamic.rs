@@ -0,0 +1,812 @@
+use super::{
+    draw_symbol_buffer, needs_projection, project_geometry, selection_id_at_point, DrawingMode,
+    Layer, ResolutionRange,
+};
+use crate::crs::Crs;
+use crate::feature::{Feature, FeatureId};
+use crate::geometry::{bbox_center, merge_bbox, Bounds, TransformPoints};
+use crate::gl::GlBuffer;
+use crate::map::MapPosition;
+use crate::render_target::RenderTarget;
+use crate::symbol::Symbol;
+use crate::{BoundingBox, Point3};
+use glow::Context;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Number of vertices and indices the GPU buffers are created with.
+const MIN_CAPACITY: usize = 1024;
+
+/// Gaps between changed parts of the buffers up to this number of elements are uploaded along
+/// with the changes to make fewer calls.
+const MAX_DIRTY_GAP: usize = 256;
+
+/// Changes the vertices of a feature after they are produced by the symbol.
+pub type VertexStyle<V> = Box<dyn Fn(&mut V)>;
+
+/// Layer for features that are added, removed and changed often.
+///
+/// Unlike `StaticLayer`, which rebuilds its buffer on every change, the dynamic layer converts
+/// only the new and updated features and writes their vertices into the existing GPU buffers.
+/// The buffers grow geometrically, and vertices of removed features are compacted away once
+/// they take more than half of the buffers.
+pub struct DynamicLayer<G, S: Symbol<G>> {
+    symbol: S,
+    /// Features by the id passed to the symbol, which is their position in the list.
+    slots: Vec<Option<Slot<G, S::Vertex>>>,
+    free_slots: Vec<usize>,
+    slot_by_id: HashMap<FeatureId, usize>,
+    /// Id for `add_geometry`, the next after the biggest numeric id ever added.
+    next_id: i64,
+    /// Slots of the features that must be converted into vertices.
+    pending: Vec<usize>,
+    /// Copies of the buffer contents, used to move the data when the buffers are reallocated
+    /// or compacted.
+    vertices: Vec<S::Vertex>,
+    indices: Vec<u32>,
+    /// Number of indices in `indices` that belong to removed features.
+    garbage_indices: usize,
+    /// Parts of the vertex and index lists that differ from the GPU buffers.
+    dirty_vertices: Vec<Range<usize>>,
+    dirty_indices: Vec<Range<usize>>,
+    context: Option<Rc<Context>>,
+    buffer: Option<GlBuffer>,
+    vertex_capacity: usize,
+    index_capacity: usize,
+    /// Extent of the converted features in the map CRS, used for culling.
+    extent: Option<BoundingBox>,
+    /// Point the vertex positions are relative to. It is set by the first converted feature.
+    origin: Option<Point3>,
+    crs: Option<Crs>,
+    resolution_range: ResolutionRange,
+    /// The map CRS the vertices were built for.
+    buffer_crs: Option<Crs>,
+    /// World width the geometries were unwrapped with.
+    buffer_world_width: Option<f64>,
+}
+
+struct Slot<G, V> {
+    feature: Feature<G>,
+    /// Vertices and indices of the feature, `None` if it is not converted yet.
+    ranges: Option<(Range<usize>, Range<usize>)>,
+    /// The geometry changed since the vertices were produced.
+    changed: bool,
+    style: Option<VertexStyle<V>>,
+}
+
+impl<G, S: Symbol<G>> DynamicLayer<G, S> {
+    pub fn new(symbol: S) -> Self {
+        Self {
+            symbol,
+            slots: vec![],
+            free_slots: vec![],
+            slot_by_id: HashMap::new(),
+            next_id: 0,
+            pending: vec![],
+            vertices: vec![],
+            indices: vec![],
+            garbage_indices: 0,
+            dirty_vertices: vec![],
+            dirty_indices: vec![],
+            context: None,
+            buffer: None,
+            vertex_capacity: 0,
+            index_capacity: 0,
+            extent: None,
+            origin: None,
+            crs: None,
+            resolution_range: ResolutionRange::default(),
+            buffer_crs: None,
+            buffer_world_width: None,
+        }
+    }
+
+    /// Sets the CRS of the features. If it differs from the map CRS, the features are
+    /// reprojected when they are drawn.
+    pub fn with_crs(mut self, crs: Crs) -> Self {
+        self.set_crs(Some(crs));
+        self
+    }
+
+    pub fn with_resolution_range(mut self, range: ResolutionRange) -> Self {
+        self.resolution_range = range;
+        self
+    }
+
+    pub fn set_resolution_range(&mut self, range: ResolutionRange) {
+        self.resolution_range = range;
+    }
+
+    pub fn set_crs(&mut self, crs: Option<Crs>) {
+        self.crs = crs;
+        self.reset();
+    }
+
+    /// Deletes the GPU buffers. All features are converted again the next time the layer is
+    /// drawn.
+    pub fn clean(&mut self) {
+        if let (Some(gl), Some(buffer)) = (&self.context, &self.buffer) {
+            buffer.delete(gl);
+        }
+
+        self.buffer = None;
+        self.context = None;
+        self.reset();
+    }
+
+    /// Drops the converted vertices and marks all the features as pending.
+    fn reset(&mut self) {
+        self.vertices.clear();
+        self.indices.clear();
+        self.garbage_indices = 0;
+        self.dirty_vertices.clear();
+        self.dirty_indices.clear();
+        self.extent = None;
+        self.origin = None;
+        self.buffer_crs = None;
+        self.buffer_world_width = None;
+
+        self.pending.clear();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(slot) = slot {
+                slot.ranges = None;
+                slot.changed = true;
+                self.pending.push(index);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slot_by_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slot_by_id.is_empty()
+    }
+
+    pub fn features(&self) -> impl Iterator<Item = &Feature<G>> {
+        self.slots.iter().flatten().map(|slot| &slot.feature)
+    }
+
+    pub fn feature(&self, id: &FeatureId) -> Option<&Feature<G>> {
+        let slot = *self.slot_by_id.get(id)?;
+        self.slots[slot].as_ref().map(|slot| &slot.feature)
+    }
+
+    /// Adds the feature. A feature with the same id is replaced.
+    pub fn add(&mut self, feature: Feature<G>) {
+        self.remove(feature.id());
+        if let FeatureId::Number(id) = feature.id() {
+            self.next_id = self.next_id.max(*id + 1);
+        }
+
+        let slot = match self.free_slots.pop() {
+            Some(slot) => slot,
+            None => {
+                self.slots.push(None);
+                self.slots.len() - 1
+            }
+        };
+        self.slot_by_id.insert(feature.id().clone(), slot);
+        self.slots[slot] = Some(Slot {
+            feature,
+            ranges: None,
+            changed: true,
+            style: None,
+        });
+        self.pending.push(slot);
+    }
+
+    /// Adds a feature without attributes and returns its id. The id is the next after the
+    /// biggest numeric id ever added to the layer.
+    pub fn add_geometry(&mut self, geometry: G) -> FeatureId {
+        let id = self.next_id;
+        self.add(Feature::new(id, geometry));
+        FeatureId::Number(id)
+    }
+
+    /// Removes the feature. Its triangles are made degenerate, and the vertices stay in the
+    /// buffer until it is compacted.
+    pub fn remove(&mut self, id: &FeatureId) -> Option<Feature<G>> {
+        let index = self.slot_by_id.remove(id)?;
+        let slot = self.slots[index].take()?;
+        self.free_slots.push(index);
+
+        if let Some((_, indices)) = slot.ranges {
+            self.release_indices(indices);
+        }
+
+        Some(slot.feature)
+    }
+
+    /// Replaces the geometry of the feature and returns the previous one. Only this feature is
+    /// converted again, and its vertices are written over the old ones if they fit.
+    pub fn update(&mut self, id: &FeatureId, geometry: G) -> Option<G> {
+        let index = *self.slot_by_id.get(id)?;
+        let slot = self.slots[index].as_mut()?;
+        let previous = std::mem::replace(slot.feature.geometry_mut(), geometry);
+        if !slot.changed {
+            slot.changed = true;
+            self.pending.push(index);
+        }
+
+        Some(previous)
+    }
+
+    /// Sets the style of the feature, replacing the previous one. The style is applied to the
+    /// vertices the feature already has, and to the new ones when its geometry is updated. If
+    /// the feature already had a style, it is converted again on the next frame so that nothing
+    /// of the previous style is left. Returns `false` if there is no such feature.
+    ///
+    /// Only the vertex attributes that do not depend on the geometry shape can be changed this
+    /// way, e.g. the color of any vertex or the size of `CirclePointVertex`.
+    pub fn update_style(
+        &mut self,
+        id: &FeatureId,
+        style: impl Fn(&mut S::Vertex) + 'static,
+    ) -> bool {
+        let index = match self.slot_by_id.get(id) {
+            Some(index) => *index,
+            None => return false,
+        };
+        let slot = self.slots[index].as_mut().unwrap();
+
+        if !slot.changed {
+            if slot.style.is_some() {
+                // The previous style can't be undone, so the feature is converted again.
+                slot.changed = true;
+                self.pending.push(index);
+            } else if let Some((vertices, _)) = &slot.ranges {
+                self.vertices[vertices.clone()].iter_mut().for_each(&style);
+                mark_dirty(&mut self.dirty_vertices, vertices.clone());
+            }
+        }
+        slot.style = Some(Box::new(style));
+        true
+    }
+
+    /// Removes the style of the feature. The feature is converted again to restore the symbol
+    /// style.
+    pub fn clear_style(&mut self, id: &FeatureId) {
+        if let Some(index) = self.slot_by_id.get(id) {
+            if let Some(slot) = &mut self.slots[*index] {
+                if slot.style.take().is_some() && !slot.changed {
+                    slot.changed = true;
+                    self.pending.push(*index);
+                }
+            }
+        }
+    }
+
+    /// Makes the triangles of the index range degenerate.
+    fn release_indices(&mut self, range: Range<usize>) {
+        for index in &mut self.indices[range.clone()] {
+            *index = 0;
+        }
+        self.garbage_indices += range.len();
+        mark_dirty(&mut self.dirty_indices, range);
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free_slots.clear();
+        self.slot_by_id.clear();
+        self.next_id = 0;
+        self.reset();
+    }
+
+    pub fn draw_with_context(
+        &self,
+        gl: &Context,
+        position: &MapPosition,
+        width: u32,
+        height: u32,
+        drawing_mode: DrawingMode,
+        opacity: f32,
+    ) {
+        if let Some(buffer) = &self.buffer {
+            draw_symbol_buffer(
+                gl,
+                &self.symbol,
+                buffer,
+                self.extent.as_ref(),
+                self.origin.unwrap_or([0.0, 0.0, 0.0]),
+                position,
+                (width, height),
+                drawing_mode,
+                opacity,
+            );
+        }
+    }
+}
+
+impl<G: Bounds + TransformPoints, S: Symbol<G>> DynamicLayer<G, S>
+where
+    S::Vertex: Copy,
+{
+    fn set_context(&mut self, gl: Rc<Context>, position: &MapPosition) {
+        if let Some(context) = &self.context {
+            if !Rc::ptr_eq(context, &gl) {
+                self.clean();
+            }
+        }
+        if self.buffer_crs.as_ref() != Some(position.crs())
+            || self.buffer_world_width != position.world_width()
+        {
+            self.reset();
+            self.buffer_crs = Some(position.crs().clone());
+            self.buffer_world_width = position.world_width();
+        }
+
+        self.symbol.compile(&gl);
+        self.context = Some(gl);
+    }
+
+    /// Converts the pending features and compacts the vertex lists if needed.
+    fn prepare_vertices(&mut self, position: &MapPosition) {
+        for index in std::mem::take(&mut self.pending) {
+            let slot = match &self.slots[index] {
+                Some(slot) if slot.changed => slot,
+                _ => continue,
+            };
+
+            let crs = self.crs.as_ref();
+            let projected = if needs_projection(crs, position) {
+                Some(project_geometry(slot.feature.geometry(), crs, position))
+            } else {
+                None
+            };
+            let geometry = projected
+                .as_ref()
+                .unwrap_or_else(|| slot.feature.geometry());
+
+            let bbox = geometry.bbox();
+            self.extent = merge_bbox(self.extent, bbox);
+            let origin = *self
+                .origin
+                .get_or_insert_with(|| bbox.map(|bbox| bbox_center(&bbox)).unwrap_or_default());
+
+            let (mut vertices, indices) = self.symbol.convert(geometry, index as u32, origin);
+            if let Some(style) = &slot.style {
+                vertices.iter_mut().for_each(style);
+            }
+            let indices = indices.unwrap_or_else(|| (0..vertices.len() as u32).collect());
+
+            let ranges = match slot.ranges.clone() {
+                Some((vertex_range, index_range))
+                    if vertices.len() <= vertex_range.len()
+                        && indices.len() <= index_range.len() =>
+                {
+                    self.write_vertices(vertex_range.start, index_range, &vertices, &indices)
+                }
+                previous => {
+                    if let Some((_, index_range)) = previous {
+                        self.release_indices(index_range);
+                    }
+                    let index_range = self.indices.len()..self.indices.len() + indices.len();
+                    self.vertices.extend_from_slice(&vertices);
+                    self.indices.resize(index_range.end, 0);
+                    self.write_vertices(
+                        self.vertices.len() - vertices.len(),
+                        index_range,
+                        &vertices,
+                        &indices,
+                    )
+                }
+            };
+
+            if let Some(slot) = &mut self.slots[index] {
+                slot.ranges = Some(ranges);
+                slot.changed = false;
+            }
+        }
+
+        if self.garbage_indices > 0 && self.garbage_indices * 2 >= self.indices.len() {
+            self.compact();
+        }
+    }
+
+    /// Writes the vertices of a feature starting from `vertex_start`, and its indices into the
+    /// `index_range`. Indices left in the range are released. Returns the ranges the feature
+    /// takes now.
+    fn write_vertices(
+        &mut self,
+        vertex_start: usize,
+        index_range: Range<usize>,
+        vertices: &[S::Vertex],
+        indices: &[u32],
+    ) -> (Range<usize>, Range<usize>) {
+        let vertex_range = vertex_start..vertex_start + vertices.len();
+        self.vertices[vertex_range.clone()].copy_from_slice(vertices);
+
+        let used_indices = index_range.start..index_range.start + indices.len();
+        for (target, index) in self.indices[used_indices.clone()].iter_mut().zip(indices) {
+            *target = index + vertex_start as u32;
+        }
+        self.release_indices(used_indices.end..index_range.end);
+
+        mark_dirty(&mut self.dirty_vertices, vertex_range.clone());
+        mark_dirty(&mut self.dirty_indices, used_indices.clone());
+        (vertex_range, used_indices)
+    }
+
+    /// Moves the vertices of the features to the start of the lists, dropping the vertices of
+    /// removed features. The features are not converted again.
+    fn compact(&mut self) {
+        let mut slots: Vec<&mut Slot<G, S::Vertex>> = self
+            .slots
+            .iter_mut()
+            .flatten()
+            .filter(|slot| slot.ranges.is_some())
+            .collect();
+        slots.sort_by_key(|slot| slot.ranges.as_ref().unwrap().0.start);
+
+        let mut vertices = Vec::with_capacity(self.vertices.len());
+        let mut indices = Vec::with_capacity(self.indices.len() - self.garbage_indices);
+        for slot in slots {
+            let (vertex_range, index_range) = slot.ranges.take().unwrap();
+            let new_start = vertices.len();
+            vertices.extend_from_slice(&self.vertices[vertex_range.clone()]);
+            let index_start = indices.len();
+            indices.extend(
+                self.indices[index_range]
+                    .iter()
+                    .map(|i| *i - vertex_range.start as u32 + new_start as u32),
+            );
+            slot.ranges = Some((new_start..vertices.len(), index_start..indices.len()));
+        }
+
+        self.vertices = vertices;
+        self.indices = indices;
+        self.garbage_indices = 0;
+        self.mark_all_dirty();
+    }
+
+    fn mark_all_dirty(&mut self) {
+        self.dirty_vertices.clear();
+        self.dirty_indices.clear();
+        mark_dirty(&mut self.dirty_vertices, 0..self.vertices.len());
+        mark_dirty(&mut self.dirty_indices, 0..self.indices.len());
+    }
+
+    /// Writes the changed parts of the vertex lists into the GPU buffers, reallocating them if
+    /// they are too small.
+    fn upload(&mut self, gl: &Context) {
+        let fits = self.buffer.is_some()
+            && self.vertices.len() <= self.vertex_capacity
+            && self.indices.len() <= self.index_capacity;
+        if !fits {
+            if let Some(buffer) = &self.buffer {
+                buffer.delete(gl);
+            }
+
+            self.vertex_capacity = grown_capacity(self.vertex_capacity, self.vertices.len());
+            self.index_capacity = grown_capacity(self.index_capacity, self.indices.len());
+            self.buffer = Some(GlBuffer::with_capacity::<S::Vertex>(
+                gl,
+                self.vertex_capacity,
+                self.index_capacity,
+            ));
+            self.mark_all_dirty();
+        }
+
+        let buffer = self.buffer.as_mut().unwrap();
+        for range in coalesce_ranges(std::mem::take(&mut self.dirty_vertices)) {
+            buffer.update_vertices(gl, range.start, &self.vertices[range]);
+        }
+        for range in coalesce_ranges(std::mem::take(&mut self.dirty_indices)) {
+            buffer.update_indices(gl, range.start, &self.indices[range]);
+        }
+        buffer.vertex_count = self.indices.len() as u32;
+    }
+}
+
+impl<G: Bounds + TransformPoints, S: Symbol<G>> Layer for DynamicLayer<G, S>
+where
+    S::Vertex: Copy,
+{
+    fn draw(&mut self, target: &RenderTarget, position: &MapPosition, opacity: f32) {
+        if self.is_empty() && self.buffer.is_none() {
+            return;
+        }
+
+        let gl = target.context();
+        self.set_context(gl.clone(), position);
+        self.prepare_vertices(position);
+        self.upload(&gl);
+
+        let (width, height) = target.get_dimensions();
+        self.draw_with_context(&gl, position, width, height, DrawingMode::Normal, opacity);
+    }
+
+    fn feature_at_point(
+        &self,
+        target: &RenderTarget,
+        screen_position: [i32; 2],
+        map_position: &MapPosition,
+    ) -> Option<FeatureId> {
+        let id = selection_id_at_point(target, screen_position, |gl, width, height| {
+            self.draw_with_context(gl, map_position, width, height, DrawingMode::Selection, 1.0)
+        })?;
+        self.slots
+            .get(id as usize)?
+            .as_ref()
+            .map(|slot| slot.feature.id().clone())
+    }
+
+    fn extent(&self) -> Option<BoundingBox> {
+        self.features()
+            .fold(None, |extent, feature| merge_bbox(extent, feature.bbox()))
+    }
+
+    fn crs(&self) -> Option<&Crs> {
+        self.crs.as_ref()
+    }
+
+    fn resolution_range(&self) -> ResolutionRange {
+        self.resolution_range
+    }
+}
+
+impl<G, S: Symbol<G>> Drop for DynamicLayer<G, S> {
+    fn drop(&mut self) {
+        self.clean();
+    }
+}
+
+/// Adds the range to the list, merging it with the last one if they touch.
+fn mark_dirty(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    if range.is_empty() {
+        return;
+    }
+
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+/// Sorts the ranges and merges the ones that overlap or are closer than `MAX_DIRTY_GAP`.
+fn coalesce_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end + MAX_DIRTY_GAP => {
+                last.end = last.end.max(range.end)
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+fn grown_capacity(capacity: usize, required: usize) -> usize {
+    let mut capacity = capacity.max(MIN_CAPACITY);
+    while capacity < required {
+        capacity *= 2;
+    }
+    capacity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbol::{LineSymbol, LineVertex};
+    use crate::Polyline;
+
+    fn layer() -> DynamicLayer<Polyline, LineSymbol> {
+        DynamicLayer::new(LineSymbol {
+            width: 2.0,
+            color: [0.0, 0.0, 0.0, 1.0],
+            program: None,
+        })
+    }
+
+    fn line(x: f64) -> Polyline {
+        vec![[x, 0.0, 0.0], [x + 1.0, 1.0, 0.0]]
+    }
+
+    #[test]
+    fn only_new_features_are_converted() {
+        let mut layer = layer();
+        let position = MapPosition::default();
+        let first = layer.add_geometry(line(0.0));
+        layer.prepare_vertices(&position);
+        let first_len = layer.vertices.len();
+        assert_eq!(Some(&(0..first_len)), layer.dirty_vertices.last());
+
+        layer.dirty_vertices.clear();
+        layer.dirty_indices.clear();
+        let second = layer.add_geometry(line(10.0));
+        layer.prepare_vertices(&position);
+
+        assert_eq!(FeatureId::Number(1), second);
+        assert_eq!(1, layer.dirty_vertices.len());
+        assert_eq!(first_len..layer.vertices.len(), layer.dirty_vertices[0]);
+        let ranges = |id| {
+            layer.slots[layer.slot_by_id[id]]
+                .as_ref()
+                .unwrap()
+                .ranges
+                .clone()
+        };
+        assert_eq!(0..first_len, ranges(&first).unwrap().0);
+    }
+
+    #[test]
+    fn removal_compacts_vertices() {
+        let mut layer = layer();
+        let position = MapPosition::default();
+        let ids: Vec<_> = (0..4).map(|i| layer.add_geometry(line(i as f64))).collect();
+        layer.prepare_vertices(&position);
+        let total = layer.indices.len();
+
+        layer.remove(&ids[0]);
+        assert_eq!(total / 4, layer.garbage_indices);
+        assert!(layer.indices[..total / 4].iter().all(|i| *i == 0));
+        layer.prepare_vertices(&position);
+        assert_eq!(total, layer.indices.len());
+
+        layer.remove(&ids[2]);
+        layer.prepare_vertices(&position);
+        assert_eq!(total / 2, layer.indices.len());
+        assert_eq!(0, layer.garbage_indices);
+
+        let remaining: Vec<_> = layer.features().map(|f| f.id().clone()).collect();
+        assert_eq!(vec![ids[1].clone(), ids[3].clone()], remaining);
+        let (vertices, indices) = layer.slots[layer.slot_by_id[&ids[3]]]
+            .as_ref()
+            .unwrap()
+            .ranges
+            .clone()
+            .unwrap();
+        assert_eq!(layer.vertices.len(), vertices.end);
+        assert!(layer.indices[indices]
+            .iter()
+            .all(|i| vertices.contains(&(*i as usize))));
+    }
+
+    #[test]
+    fn slots_are_reused() {
+        let mut layer = layer();
+        let a = layer.add_geometry(line(0.0));
+        layer.add_geometry(line(1.0));
+        layer.remove(&a);
+        layer.add(Feature::new("c", line(2.0)));
+
+        assert_eq!(2, layer.slots.len());
+        assert_eq!(2, layer.len());
+        assert!(layer.feature(&FeatureId::from("c")).is_some());
+    }
+
+    #[test]
+    fn update_in_place() {
+        let mut layer = layer();
+        let position = MapPosition::default();
+        let ids: Vec<_> = (0..3).map(|i| layer.add_geometry(line(i as f64))).collect();
+        layer.prepare_vertices(&position);
+        let ranges = |layer: &DynamicLayer<_, _>, id| {
+            layer.slots[layer.slot_by_id[id]]
+                .as_ref()
+                .unwrap()
+                .ranges
+                .clone()
+                .unwrap()
+        };
+        let before: Vec<_> = ids.iter().map(|id| ranges(&layer, id)).collect();
+        layer.dirty_vertices.clear();
+        layer.dirty_indices.clear();
+
+        let previous = layer.update(&ids[1], line(5.0)).unwrap();
+        assert_eq!(line(1.0), previous);
+        layer.prepare_vertices(&position);
+
+        let after: Vec<_> = ids.iter().map(|id| ranges(&layer, id)).collect();
+        assert_eq!(before, after);
+        assert_eq!(vec![before[1].0.clone()], layer.dirty_vertices);
+        assert_eq!(0, layer.garbage_indices);
+    }
+
+    #[test]
+    fn update_moves_bigger_geometry() {
+        let mut layer = layer();
+        let position = MapPosition::default();
+        let a = layer.add_geometry(line(0.0));
+        let b = layer.add_geometry(line(1.0));
+        layer.prepare_vertices(&position);
+        let index_count = layer.indices.len();
+
+        let mut longer = line(0.0);
+        longer.push([3.0, 0.0, 0.0]);
+        layer.update(&a, longer);
+        layer.prepare_vertices(&position);
+
+        let slot = layer.slots[layer.slot_by_id[&a]].as_ref().unwrap();
+        let (vertices, indices) = slot.ranges.clone().unwrap();
+        assert_eq!(layer.vertices.len(), vertices.end);
+        assert_eq!(index_count / 2, layer.garbage_indices);
+        assert!(layer.indices[indices]
+            .iter()
+            .all(|i| vertices.contains(&(*i as usize))));
+        assert_eq!(line(1.0), *layer.feature(&b).unwrap().geometry());
+    }
+
+    #[test]
+    fn style_is_kept_on_update() {
+        let mut layer = layer();
+        let position = MapPosition::default();
+        let a = layer.add_geometry(line(0.0));
+        let b = layer.add_geometry(line(1.0));
+        layer.prepare_vertices(&position);
+
+        let red = [1.0, 0.0, 0.0, 1.0];
+        assert!(layer.update_style(&a, move |vertex: &mut LineVertex| vertex.color = red));
+        assert!(!layer.update_style(&FeatureId::from("missing"), |_| {}));
+        let color_of = |layer: &DynamicLayer<Polyline, LineSymbol>, id| {
+            let (vertices, _) = layer.slots[layer.slot_by_id[id]]
+                .as_ref()
+                .unwrap()
+                .ranges
+                .clone()
+                .unwrap();
+            layer.vertices[vertices.start].color
+        };
+        assert_eq!(red, color_of(&layer, &a));
+        assert_eq!([0.0, 0.0, 0.0, 1.0], color_of(&layer, &b));
+
+        layer.update(&a, line(2.0));
+        layer.prepare_vertices(&position);
+        assert_eq!(red, color_of(&layer, &a));
+
+        layer.clear_style(&a);
+        layer.prepare_vertices(&position);
+        assert_eq!([0.0, 0.0, 0.0, 1.0], color_of(&layer, &a));
+    }
+
+    #[test]
+    fn style_is_replaced() {
+        let mut layer = layer();
+        let position = MapPosition::default();
+        let id = layer.add_geometry(line(0.0));
+        layer.prepare_vertices(&position);
+
+        let red = [1.0, 0.0, 0.0, 1.0];
+        layer.update_style(&id, move |vertex: &mut LineVertex| vertex.color = red);
+        layer.prepare_vertices(&position);
+
+        let opacity = |vertex: &mut LineVertex| vertex.color[3] = 0.5;
+        assert!(layer.update_style(&id, opacity));
+        layer.prepare_vertices(&position);
+
+        let (vertices, _) = layer.slots[layer.slot_by_id[&id]]
+            .as_ref()
+            .unwrap()
+            .ranges
+            .clone()
+            .unwrap();
+        for vertex in &layer.vertices[vertices] {
+            assert_eq!([0.0, 0.0, 0.0, 0.5], vertex.color);
+        }
+    }
+
+    #[test]
+    fn ranges_are_coalesced() {
+        assert_eq!(
+            vec![0..20, 1000..1010],
+            coalesce_ranges(vec![1000..1010, 10..20, 0..5])
+        );
+    }
+
+    #[test]
+    fn capacity_grows_geometrically() {
+        assert_eq!(MIN_CAPACITY, grown_capacity(0, 10));
+        assert_eq!(
+            4 * MIN_CAPACITY,
+            grown_capacity(MIN_CAPACITY, 3 * MIN_CAPACITY)
+        );
+        assert_eq!(2048, grown_capacity(2048, 2000));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::crs::Crs;
+use crate::feature::FeatureId;
+use crate::geometry::merge_bbox;
+use crate::layer::{Layer, LayerId, LayerList, ResolutionRange};
+use crate::map::MapPosition;
+use crate::render_target::RenderTarget;
+use crate::BoundingBox;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Layer consisting of other layers, possibly other groups. Visibility, opacity and
+/// resolution range of the group apply to all of its children.
+#[derive(Default)]
+pub struct LayerGroup {
+    layers: LayerList,
+    resolution_range: ResolutionRange,
+    crs: Option<Crs>,
+}
+
+/// Result of the hit test on a group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerHit {
+    /// Ids of the layers from the direct child of the group down to the layer with the
+    /// feature, if the feature belongs to a nested group.
+    pub path: Vec<LayerId>,
+    pub feature: FeatureId,
+}
+
+impl LayerHit {
+    /// Id of the layer the feature belongs to.
+    pub fn layer_id(&self) -> LayerId {
+        *self.path.last().expect("hit path is never empty")
+    }
+}
+
+impl LayerGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_resolution_range(mut self, range: ResolutionRange) -> Self {
+        self.resolution_range = range;
+        self
+    }
+
+    pub fn set_resolution_range(&mut self, range: ResolutionRange) {
+        self.resolution_range = range;
+    }
+
+    /// Sets the CRS the extent of the group is calculated in. Extents of the children are
+    /// transformed into it, children without CRS are considered to be in it already.
+    pub fn with_crs(mut self, crs: Crs) -> Self {
+        self.crs = Some(crs);
+        self
+    }
+
+    /// Adds the layer on top of all the other layers of the group. If the CRS of the group is
+    /// not set, the CRS of the layer is used.
+    pub fn add(&mut self, layer: Rc<RefCell<dyn Layer>>) -> LayerId {
+        if self.crs.is_none() {
+            self.crs = layer.borrow().crs().cloned();
+        }
+        self.layers.add(layer)
+    }
+
+    pub fn layers(&self) -> &LayerList {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut LayerList {
+        &mut self.layers
+    }
+
+    /// Finds the topmost visible feature under the cursor and returns the path to the layer it
+    /// belongs to. Children are checked from the top to the bottom.
+    pub fn hit_test(
+        &self,
+        target: &RenderTarget,
+        screen_position: [i32; 2],
+        map_position: &MapPosition,
+    ) -> Option<LayerHit> {
+        let resolution = map_position.resolution();
+        for entry in self.layers.iter().rev() {
+            if entry.opacity_at(resolution) <= 0.0 {
+                continue;
+            }
+
+            let layer = entry.layer().borrow();
+            let hit = match layer.as_group() {
+                Some(group) => group.hit_test(target, screen_position, map_position),
+                None => layer
+                    .feature_at_point(target, screen_position, map_position)
+                    .map(|feature| LayerHit {
+                        path: vec![],
+                        feature,
+                    }),
+            };
+
+            if let Some(mut hit) = hit {
+                hit.path.insert(0, entry.id());
+                return Some(hit);
+            }
+        }
+
+        None
+    }
+}
+
+impl Layer for LayerGroup {
+    fn draw(&mut self, target: &RenderTarget, position: &MapPosition, opacity: f32) {
+        let resolution = position.resolution();
+        for entry in self.layers.iter() {
+            let child_opacity = opacity * entry.opacity_at(resolution);
+            if child_opacity > 0.0 {
+                entry
+                    .layer()
+                    .borrow_mut()
+                    .draw(target, position, child_opacity);
+            }
+        }
+    }
+
+    /// Returns the id of the topmost feature under the cursor. Use [`LayerGroup::hit_test`] to
+    /// find out which layer the feature belongs to.
+    fn feature_at_point(
+        &self,
+        target: &RenderTarget,
+        screen_position: [i32; 2],
+        map_position: &MapPosition,
+    ) -> Option<FeatureId> {
+        self.hit_test(target, screen_position, map_position)
+            .map(|hit| hit.feature)
+    }
+
+    fn extent(&self) -> Option<BoundingBox> {
+        self.layers.iter().fold(None, |extent, entry| {
+            let layer = entry.layer().borrow();
+            let layer_extent = match (layer.crs(), &self.crs) {
+                (Some(layer_crs), Some(crs)) => layer
+                    .extent()
+                    .map(|bbox| layer_crs.transform_bbox(crs, bbox)),
+                _ => layer.extent(),
+            };
+            merge_bbox(extent, layer_extent)
+        })
+    }
+
+    fn crs(&self) -> Option<&Crs> {
+        self.crs.as_ref()
+    }
+
+    fn resolution_range(&self) -> ResolutionRange {
+        self.resolution_range
+    }
+
+    fn as_group(&self) -> Option<&LayerGroup> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct BoxLayer(BoundingBox, Option<Crs>);
+
+    impl Layer for BoxLayer {
+        fn draw(&mut self, _target: &RenderTarget, _position: &MapPosition, _opacity: f32) {}
+
+        fn extent(&self) -> Option<BoundingBox> {
+            Some(self.0)
+        }
+
+        fn crs(&self) -> Option<&Crs> {
+            self.1.as_ref()
+        }
+    }
+
+    #[test]
+    fn extent_of_children() {
+        let mut group = LayerGroup::new();
+        group.add(Rc::new(RefCell::new(BoxLayer(
+            [0.0, 0.0, 10.0, 10.0],
+            Some(Crs::wgs84()),
+        ))));
+        group.add(Rc::new(RefCell::new(BoxLayer(
+            [-5.0, 5.0, 5.0, 20.0],
+            Some(Crs::wgs84()),
+        ))));
+
+        assert_eq!(Some(&Crs::wgs84()), group.crs());
+        assert_eq!(Some([-5.0, 0.0, 10.0, 20.0]), group.extent());
+    }
+
+    #[test]
+    fn nested_visibility() {
+        let mut inner = LayerGroup::new();
+        let pipes = inner.add(Rc::new(RefCell::new(BoxLayer([0.0; 4], None))));
+        let inner = Rc::new(RefCell::new(inner));
+
+        let mut outer =
+            LayerGroup::new().with_resolution_range(ResolutionRange::new(None, Some(10.0)));
+        let water = outer.add(inner.clone());
+        outer.layers_mut().set_opacity(water, 0.5);
+
+        let mut layers = LayerList::new();
+        let utilities = layers.add(Rc::new(RefCell::new(outer)));
+        let entry = layers.entry(utilities).unwrap();
+        assert_eq!(1.0, entry.opacity_at(5.0));
+        assert_eq!(0.0, entry.opacity_at(20.0));
+
+        let outer = entry.layer().borrow();
+        let group = outer.as_group().unwrap();
+        assert_eq!(0.5, group.layers().entry(water).unwrap().opacity_at(5.0));
+
+        inner.borrow_mut().layers_mut().set_visible(pipes, false);
+        let inner = inner.borrow();
+        assert_eq!(0.0, inner.layers().entry(pipes).unwrap().opacity_at(5.0));
+    }
+}
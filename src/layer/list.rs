@@ -0,0 +1,219 @@
+use crate::layer::Layer;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Identifier of a layer in a `LayerList`. It does not change when the layers are reordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(usize);
+
+/// Layer together with its display settings.
+pub struct LayerEntry {
+    id: LayerId,
+    layer: Rc<RefCell<dyn Layer>>,
+    visible: bool,
+    opacity: f32,
+}
+
+impl LayerEntry {
+    pub fn id(&self) -> LayerId {
+        self.id
+    }
+
+    pub fn layer(&self) -> &Rc<RefCell<dyn Layer>> {
+        &self.layer
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Returns `true` if the layer is visible and not fully transparent.
+    pub fn is_drawn(&self) -> bool {
+        self.visible && self.opacity > 0.0
+    }
+
+    /// Opacity the layer is drawn with at the given resolution, taking into account the
+    /// visibility of the layer and its resolution range. 0 means that the layer is not drawn.
+    pub fn opacity_at(&self, resolution: f32) -> f32 {
+        if !self.is_drawn() {
+            return 0.0;
+        }
+
+        self.opacity
+            * self
+                .layer
+                .borrow()
+                .resolution_range()
+                .opacity_at(resolution)
+    }
+}
+
+/// Ordered list of layers. Layers are drawn from the first to the last, so the last layer is
+/// displayed on top of the others.
+#[derive(Default)]
+pub struct LayerList {
+    entries: Vec<LayerEntry>,
+    next_id: usize,
+}
+
+impl LayerList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the layer on top of all the others.
+    pub fn add(&mut self, layer: Rc<RefCell<dyn Layer>>) -> LayerId {
+        self.insert(self.entries.len(), layer)
+    }
+
+    /// Inserts the layer at the given position in the drawing order. If the `index` is bigger
+    /// than the number of layers, the layer is added on top.
+    pub fn insert(&mut self, index: usize, layer: Rc<RefCell<dyn Layer>>) -> LayerId {
+        self.next_id += 1;
+        let id = LayerId(self.next_id);
+        self.entries.insert(
+            index.min(self.entries.len()),
+            LayerEntry {
+                id,
+                layer,
+                visible: true,
+                opacity: 1.0,
+            },
+        );
+
+        id
+    }
+
+    pub fn remove(&mut self, id: LayerId) -> Option<Rc<RefCell<dyn Layer>>> {
+        let index = self.index_of(id)?;
+        Some(self.entries.remove(index).layer)
+    }
+
+    /// Moves the layer to the given position in the drawing order.
+    pub fn move_to(&mut self, id: LayerId, index: usize) -> bool {
+        match self.index_of(id) {
+            Some(current) => {
+                let entry = self.entries.remove(current);
+                self.entries.insert(index.min(self.entries.len()), entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the layer on top of all the others.
+    pub fn bring_to_front(&mut self, id: LayerId) -> bool {
+        self.move_to(id, self.entries.len())
+    }
+
+    /// Moves the layer below all the others.
+    pub fn send_to_back(&mut self, id: LayerId) -> bool {
+        self.move_to(id, 0)
+    }
+
+    pub fn index_of(&self, id: LayerId) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    pub fn get(&self, id: LayerId) -> Option<&Rc<RefCell<dyn Layer>>> {
+        self.entry(id).map(|entry| &entry.layer)
+    }
+
+    pub fn entry(&self, id: LayerId) -> Option<&LayerEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    fn entry_mut(&mut self, id: LayerId) -> Option<&mut LayerEntry> {
+        self.entries.iter_mut().find(|entry| entry.id == id)
+    }
+
+    pub fn set_visible(&mut self, id: LayerId, visible: bool) -> bool {
+        match self.entry_mut(id) {
+            Some(entry) => {
+                entry.visible = visible;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets the opacity multiplier of the layer, from 0 (transparent) to 1 (opaque).
+    pub fn set_opacity(&mut self, id: LayerId, opacity: f32) -> bool {
+        match self.entry_mut(id) {
+            Some(entry) => {
+                entry.opacity = opacity.clamp(0.0, 1.0);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Layers in the drawing order, from the bottom to the top.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &LayerEntry> {
+        self.entries.iter()
+    }
+
+    pub fn ids(&self) -> Vec<LayerId> {
+        self.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapPosition;
+    use crate::render_target::RenderTarget;
+
+    struct EmptyLayer;
+
+    impl Layer for EmptyLayer {
+        fn draw(&mut self, _target: &RenderTarget, _position: &MapPosition, _opacity: f32) {}
+    }
+
+    fn layer() -> Rc<RefCell<dyn Layer>> {
+        Rc::new(RefCell::new(EmptyLayer))
+    }
+
+    #[test]
+    fn ids_are_stable() {
+        let mut list = LayerList::new();
+        let a = list.add(layer());
+        let b = list.add(layer());
+        let c = list.insert(0, layer());
+        assert_eq!(vec![c, a, b], list.ids());
+
+        assert!(list.move_to(c, 2));
+        assert_eq!(vec![a, b, c], list.ids());
+        assert!(list.send_to_back(b));
+        assert_eq!(vec![b, a, c], list.ids());
+
+        assert!(list.remove(a).is_some());
+        assert!(list.remove(a).is_none());
+        assert_eq!(vec![b, c], list.ids());
+        assert_ne!(a, list.add(layer()));
+    }
+
+    #[test]
+    fn visibility_and_opacity() {
+        let mut list = LayerList::new();
+        let a = list.add(layer());
+        assert!(list.entry(a).unwrap().is_drawn());
+
+        list.set_opacity(a, 1.5);
+        assert_eq!(1.0, list.entry(a).unwrap().opacity());
+        list.set_visible(a, false);
+        assert!(!list.entry(a).unwrap().is_drawn());
+    }
+}
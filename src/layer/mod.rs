@@ -1,49 +1,127 @@
+use crate::crs::Crs;
+use crate::feature::{Feature, FeatureId};
+use crate::geometry::{bbox_center, merge_bbox, Bounds, TransformPoints};
 use crate::gl::GlBuffer;
 use crate::map::MapPosition;
 use crate::render_target::RenderTarget;
 use crate::symbol::Symbol;
-use glow::{Context, HasContext};
+use crate::{BoundingBox, Point3};
+use glow::{Context, HasContext, Program};
 use std::rc::Rc;
 
+mod dynamic;
+pub use dynamic::DynamicLayer;
+
+mod group;
+pub use group::{LayerGroup, LayerHit};
+
+mod list;
+pub use list::{LayerEntry, LayerId, LayerList};
+
+mod resolution_range;
+pub use resolution_range::ResolutionRange;
+
 pub trait Layer {
-    fn draw(&mut self, target: &RenderTarget, position: &MapPosition);
+    /// Draws the layer. `opacity` from 0 to 1 is the multiplier of the alpha channel of the
+    /// layer colors.
+    fn draw(&mut self, target: &RenderTarget, position: &MapPosition, opacity: f32);
+    /// Returns the id of the topmost feature drawn at the given screen position.
     fn feature_at_point(
         &self,
         _target: &RenderTarget,
         _screen_position: [i32; 2],
         _map_position: &MapPosition,
-    ) -> Option<usize> {
+    ) -> Option<FeatureId> {
+        None
+    }
+
+    /// Bounding box of all the features of the layer in the layer CRS, if it is known.
+    fn extent(&self) -> Option<BoundingBox> {
+        None
+    }
+
+    /// Coordinate reference system of the layer features. `None` means that the features are
+    /// in the map CRS.
+    fn crs(&self) -> Option<&Crs> {
+        None
+    }
+
+    /// Resolutions at which the layer is displayed. The map does not draw the layer outside of
+    /// this range.
+    fn resolution_range(&self) -> ResolutionRange {
+        ResolutionRange::default()
+    }
+
+    /// Returns the layer as a group, if it is one.
+    fn as_group(&self) -> Option<&LayerGroup> {
         None
     }
 }
 
 pub struct StaticLayer<G, S: Symbol<G>> {
-    features: Vec<G>,
+    features: Vec<Feature<G>>,
     symbol: S,
     context: Option<Rc<Context>>,
     buffer: Option<GlBuffer>,
+    /// Extent of the features in the map CRS, used for culling.
+    extent: Option<BoundingBox>,
+    /// Point the vertex positions in the buffer are relative to.
+    origin: Point3,
+    crs: Option<Crs>,
+    resolution_range: ResolutionRange,
+    /// The map CRS the buffer was built for.
+    buffer_crs: Option<Crs>,
+    /// World width the geometries in the buffer were unwrapped with.
+    buffer_world_width: Option<f64>,
 }
 
 impl<G, S: Symbol<G>> StaticLayer<G, S> {
-    pub fn new(symbol: S, features: Vec<G>) -> Self {
+    pub fn new(symbol: S, features: Vec<Feature<G>>) -> Self {
         Self {
             features,
             symbol,
             context: None,
             buffer: None,
+            extent: None,
+            origin: [0.0, 0.0, 0.0],
+            crs: None,
+            resolution_range: ResolutionRange::default(),
+            buffer_crs: None,
+            buffer_world_width: None,
         }
     }
 
+    /// Creates a layer of features without attributes, with ids equal to the indices of the
+    /// geometries.
+    pub fn from_geometries(symbol: S, geometries: Vec<G>) -> Self {
+        Self::new(symbol, Feature::from_geometries(geometries))
+    }
+
+    /// Sets the CRS of the features. If it differs from the map CRS, the features are
+    /// reprojected when the layer is drawn.
+    pub fn with_crs(mut self, crs: Crs) -> Self {
+        self.set_crs(Some(crs));
+        self
+    }
+
+    pub fn with_resolution_range(mut self, range: ResolutionRange) -> Self {
+        self.resolution_range = range;
+        self
+    }
+
+    pub fn set_resolution_range(&mut self, range: ResolutionRange) {
+        self.resolution_range = range;
+    }
+
+    pub fn set_crs(&mut self, crs: Option<Crs>) {
+        self.clean();
+        self.crs = crs;
+    }
+
     pub fn clean(&mut self) {
         if let Some(gl) = &self.context {
-            let buffer = self.buffer.as_ref().unwrap();
-            unsafe {
-                gl.delete_buffer(buffer.vertex_buffer);
-                if let Some(index_buffer) = buffer.index_buffer {
-                    gl.delete_buffer(index_buffer);
-                }
-
-                gl.delete_vertex_array(buffer.vertex_array);
+            if let Some(buffer) = &self.buffer {
+                buffer.delete(gl);
             }
 
             self.buffer = None;
@@ -51,7 +129,77 @@ impl<G, S: Symbol<G>> StaticLayer<G, S> {
         }
     }
 
-    fn set_context(&mut self, gl: Rc<Context>) {
+    pub fn features(&self) -> &[Feature<G>] {
+        &self.features
+    }
+
+    pub fn feature(&self, id: &FeatureId) -> Option<&Feature<G>> {
+        self.features.iter().find(|feature| feature.id() == id)
+    }
+
+    pub fn add(&mut self, feature: Feature<G>) {
+        self.clean();
+        self.features.push(feature);
+    }
+
+    /// Adds a feature without attributes and returns its id. The id is the next after the
+    /// biggest numeric id in the layer.
+    pub fn add_geometry(&mut self, geometry: G) -> FeatureId {
+        let next_id = self
+            .features
+            .iter()
+            .filter_map(|feature| match feature.id() {
+                FeatureId::Number(id) => Some(*id + 1),
+                FeatureId::String(_) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        self.add(Feature::new(next_id, geometry));
+        FeatureId::Number(next_id)
+    }
+
+    pub fn remove(&mut self, id: &FeatureId) -> Option<Feature<G>> {
+        let index = self
+            .features
+            .iter()
+            .position(|feature| feature.id() == id)?;
+        self.clean();
+        Some(self.features.remove(index))
+    }
+
+    pub fn draw_with_context(
+        &self,
+        gl: &Context,
+        position: &MapPosition,
+        width: u32,
+        height: u32,
+        drawing_mode: DrawingMode,
+        opacity: f32,
+    ) {
+        if let Some(buffer) = &self.buffer {
+            draw_symbol_buffer(
+                gl,
+                &self.symbol,
+                buffer,
+                self.extent.as_ref(),
+                self.origin,
+                position,
+                (width, height),
+                drawing_mode,
+                opacity,
+            );
+        }
+    }
+}
+
+impl<G: Bounds + TransformPoints, S: Symbol<G>> StaticLayer<G, S> {
+    fn set_context(&mut self, gl: Rc<Context>, position: &MapPosition) {
+        let built_for_position = self.buffer_crs.as_ref() == Some(position.crs())
+            && self.buffer_world_width == position.world_width();
+        if self.buffer.is_some() && !built_for_position {
+            self.clean();
+        }
+
         if let Some(context) = &self.context {
             if Rc::ptr_eq(context, &gl) {
                 return;
@@ -61,20 +209,48 @@ impl<G, S: Symbol<G>> StaticLayer<G, S> {
         }
 
         self.symbol.compile(&*gl);
-        self.prepare_buffer(&*gl);
+        self.prepare_buffer(&*gl, position);
 
         self.context = Some(gl);
     }
 
-    fn prepare_buffer(&mut self, gl: &Context) {
+    fn prepare_buffer(&mut self, gl: &Context, position: &MapPosition) {
         if self.buffer.is_none() {
+            let crs = self.crs.as_ref();
+            let projected: Option<Vec<G>> = if needs_projection(crs, position) {
+                Some(
+                    self.features
+                        .iter()
+                        .map(|f| project_geometry(f.geometry(), crs, position))
+                        .collect(),
+                )
+            } else {
+                None
+            };
+            let geometries: Vec<&G> = match &projected {
+                Some(projected) => projected.iter().collect(),
+                None => self.features.iter().map(Feature::geometry).collect(),
+            };
+
+            self.extent = geometries
+                .iter()
+                .fold(None, |extent, geometry| merge_bbox(extent, geometry.bbox()));
+            self.buffer_crs = Some(position.crs().clone());
+            self.buffer_world_width = position.world_width();
+            self.origin = self
+                .extent
+                .map(|bbox| bbox_center(&bbox))
+                .unwrap_or([0.0, 0.0, 0.0]);
+
             let mut vertices = vec![];
             let mut indices = vec![];
-            for (id, p) in self.features.iter().enumerate() {
-                let (mut geom_vertices, geom_indexes) = self.symbol.convert(p, id as u32);
+            for (id, p) in geometries.into_iter().enumerate() {
+                let (mut geom_vertices, geom_indexes) =
+                    self.symbol.convert(p, id as u32, self.origin);
+                let offset = vertices.len() as u32;
                 vertices.append(&mut geom_vertices);
-                if let Some(mut i) = geom_indexes {
-                    indices.append(&mut i);
+                if let Some(i) = geom_indexes {
+                    indices.extend(i.into_iter().map(|index| index + offset));
                 }
             }
 
@@ -90,94 +266,15 @@ impl<G, S: Symbol<G>> StaticLayer<G, S> {
             self.buffer = Some(GlBuffer::create(&*gl, &vertices, indices));
         }
     }
-
-    pub fn add(&mut self, feature: G) {
-        self.clean();
-        self.features.push(feature);
-    }
-
-    pub fn remove(&mut self, index: usize) {
-        self.clean();
-        self.features.remove(index);
-    }
-
-    pub fn draw_with_context(
-        &self,
-        gl: &Context,
-        position: &MapPosition,
-        width: u32,
-        height: u32,
-        drawing_mode: DrawingMode,
-    ) {
-        if self.buffer.is_none() {
-            // No object produced any vertices, so skip drawing
-            return;
-        }
-
-        let t = position.matrix();
-        let t = [
-            t[(0, 0)],
-            t[(0, 1)],
-            t[(0, 2)],
-            t[(0, 3)],
-            t[(1, 0)],
-            t[(1, 1)],
-            t[(1, 2)],
-            t[(1, 3)],
-            t[(2, 0)],
-            t[(2, 1)],
-            t[(2, 2)],
-            t[(2, 3)],
-            t[(3, 0)],
-            t[(3, 1)],
-            t[(3, 2)],
-            t[(3, 3)],
-        ];
-
-        unsafe {
-            gl.use_program(Some(*self.symbol.program().unwrap()));
-
-            let transformation_location = gl
-                .get_uniform_location(*self.symbol.program().unwrap(), "transformation")
-                .unwrap();
-            gl.uniform_matrix_4_f32_slice(Some(&transformation_location), false, &t);
-
-            let mode_location = gl
-                .get_uniform_location(*self.symbol.program().unwrap(), "mode")
-                .unwrap();
-            gl.uniform_1_u32(Some(&mode_location), drawing_mode.code());
-
-            if let Some(screen_size_location) =
-                gl.get_uniform_location(*self.symbol.program().unwrap(), "screen_size")
-            {
-                gl.uniform_2_f32(Some(&screen_size_location), width as f32, height as f32);
-            }
-
-            let buffer = self.buffer.as_ref().unwrap();
-            gl.bind_vertex_array(Some(buffer.vertex_array));
-            if buffer.index_buffer.is_some() {
-                gl.draw_elements(
-                    glow::TRIANGLES,
-                    buffer.vertex_count as i32,
-                    glow::UNSIGNED_INT,
-                    0,
-                );
-            } else {
-                gl.draw_arrays(glow::TRIANGLES, 0, buffer.vertex_count as i32);
-            }
-
-            gl.bind_vertex_array(None);
-        }
-    }
 }
 
-impl<G, S: Symbol<G>> Layer for StaticLayer<G, S> {
-    fn draw(&mut self, target: &RenderTarget, position: &MapPosition) {
+impl<G: Bounds + TransformPoints, S: Symbol<G>> Layer for StaticLayer<G, S> {
+    fn draw(&mut self, target: &RenderTarget, position: &MapPosition, opacity: f32) {
         if self.features.is_empty() {
             return;
         }
 
-        self.set_context(target.context());
+        self.set_context(target.context(), position);
 
         let (width, height) = target.get_dimensions();
         self.draw_with_context(
@@ -186,6 +283,7 @@ impl<G, S: Symbol<G>> Layer for StaticLayer<G, S> {
             width,
             height,
             DrawingMode::Normal,
+            opacity,
         );
     }
 
@@ -194,37 +292,189 @@ impl<G, S: Symbol<G>> Layer for StaticLayer<G, S> {
         target: &RenderTarget,
         screen_position: [i32; 2],
         map_position: &MapPosition,
-    ) -> Option<usize> {
-        let mut position = map_position.clone();
-        let (width, height) = target.get_dimensions();
-        position.translate_px(
-            -screen_position[0] + width as i32 / 2,
-            screen_position[1] - height as i32 / 2,
-        );
-        position.set_screen_size(1, 1);
+    ) -> Option<FeatureId> {
+        let id = selection_id_at_point(target, screen_position, |gl, width, height| {
+            self.draw_with_context(gl, map_position, width, height, DrawingMode::Selection, 1.0)
+        })?;
+        self.features
+            .get(id as usize)
+            .map(|feature| feature.id().clone())
+    }
 
-        let virtual_context = target.get_virtual_context(1, 1);
-        self.draw_with_context(
-            virtual_context.gl(),
-            &position,
-            1,
-            1,
-            DrawingMode::Selection,
-        );
-        unsafe {
-            virtual_context.gl().finish();
+    fn extent(&self) -> Option<BoundingBox> {
+        self.features.bbox()
+    }
+
+    fn crs(&self) -> Option<&Crs> {
+        self.crs.as_ref()
+    }
+
+    fn resolution_range(&self) -> ResolutionRange {
+        self.resolution_range
+    }
+}
+
+/// Returns `true` if the features in the given CRS must be transformed to be drawn at the
+/// `position`. Features are also transformed to be unwrapped across the antimeridian if the
+/// map wraps the world.
+pub(crate) fn needs_projection(crs: Option<&Crs>, position: &MapPosition) -> bool {
+    crs.is_some_and(|crs| crs != position.crs()) || position.world_width().is_some()
+}
+
+/// Transforms the geometry into the map CRS and unwraps it across the antimeridian if the map
+/// wraps the world.
+pub(crate) fn project_geometry<G: TransformPoints>(
+    geometry: &G,
+    crs: Option<&Crs>,
+    position: &MapPosition,
+) -> G {
+    let map_crs = position.crs();
+    let crs = crs.filter(|crs| *crs != map_crs);
+    let projected = match crs {
+        Some(crs) => geometry.transform_points(&mut |p| crs.transform(map_crs, p)),
+        None => geometry.transform_points(&mut |p| p),
+    };
+    match position.world_width() {
+        Some(width) => projected.unwrap_world(width),
+        None => projected,
+    }
+}
+
+/// Draws the buffer produced by the `symbol` with vertex positions relative to the `origin`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_symbol_buffer<G, S: Symbol<G>>(
+    gl: &Context,
+    symbol: &S,
+    buffer: &GlBuffer,
+    extent: Option<&BoundingBox>,
+    origin: Point3,
+    position: &MapPosition,
+    (width, height): (u32, u32),
+    drawing_mode: DrawingMode,
+    opacity: f32,
+) {
+    // Symbols can be drawn outside of the geometry by some pixels, so the visible area is
+    // expanded a little to not cull features at the screen edges
+    const CULLING_MARGIN_PX: f64 = 64.0;
+    let offsets = match extent {
+        Some(extent) => {
+            position.world_offsets(extent, CULLING_MARGIN_PX * position.resolution() as f64)
         }
+        None => vec![0.0],
+    };
+
+    if offsets.is_empty() || buffer.vertex_count == 0 {
+        return;
+    }
+
+    let program = *symbol.program().unwrap();
+    unsafe {
+        gl.use_program(Some(program));
+
+        let mode_location = gl.get_uniform_location(program, "mode").unwrap();
+        gl.uniform_1_u32(Some(&mode_location), drawing_mode.code());
+
+        if let Some(opacity_location) = gl.get_uniform_location(program, "opacity") {
+            gl.uniform_1_f32(Some(&opacity_location), opacity);
+        }
+
+        if let Some(screen_size_location) = gl.get_uniform_location(program, "screen_size") {
+            gl.uniform_2_f32(Some(&screen_size_location), width as f32, height as f32);
+        }
+
+        gl.bind_vertex_array(Some(buffer.vertex_array));
+    }
+
+    // Copies of the world to the left and right of the main one are drawn with the same
+    // buffer shifted by the world width
+    for offset in offsets {
+        let origin = [origin[0] + offset, origin[1], origin[2]];
+        draw_buffer(gl, program, buffer, position.matrix_relative_to(origin));
+    }
 
-        let pixel_value = virtual_context.pixel_value();
+    unsafe {
+        gl.bind_vertex_array(None);
+    }
+}
 
-        if pixel_value == 0 {
-            None
+fn draw_buffer(gl: &Context, program: Program, buffer: &GlBuffer, t: na::Matrix4<f32>) {
+    let t = [
+        t[(0, 0)],
+        t[(0, 1)],
+        t[(0, 2)],
+        t[(0, 3)],
+        t[(1, 0)],
+        t[(1, 1)],
+        t[(1, 2)],
+        t[(1, 3)],
+        t[(2, 0)],
+        t[(2, 1)],
+        t[(2, 2)],
+        t[(2, 3)],
+        t[(3, 0)],
+        t[(3, 1)],
+        t[(3, 2)],
+        t[(3, 3)],
+    ];
+
+    unsafe {
+        let transformation_location = gl.get_uniform_location(program, "transformation").unwrap();
+        gl.uniform_matrix_4_f32_slice(Some(&transformation_location), false, &t);
+
+        if buffer.index_buffer.is_some() {
+            gl.draw_elements(
+                glow::TRIANGLES,
+                buffer.vertex_count as i32,
+                glow::UNSIGNED_INT,
+                0,
+            );
         } else {
-            Some((pixel_value - 1) as usize)
+            gl.draw_arrays(glow::TRIANGLES, 0, buffer.vertex_count as i32);
         }
     }
 }
 
+/// Draws the layer in the selection mode into a single pixel under the cursor with `draw` and
+/// returns the id passed to `Symbol::convert` for the feature drawn there. `draw` gets the
+/// screen size to draw the whole screen with.
+pub(crate) fn selection_id_at_point(
+    target: &RenderTarget,
+    screen_position: [i32; 2],
+    draw: impl FnOnce(&Context, u32, u32),
+) -> Option<u32> {
+    let (width, height) = target.get_dimensions();
+    let virtual_context = target.get_virtual_context(1, 1);
+    let gl = virtual_context.gl();
+    unsafe {
+        let [x, y, width, height] = selection_viewport(screen_position, (width, height));
+        gl.viewport(x, y, width, height);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(glow::COLOR_BUFFER_BIT);
+    }
+
+    draw(gl, width, height);
+    unsafe {
+        gl.finish();
+    }
+
+    match virtual_context.pixel_value() {
+        0 => None,
+        value => Some(value - 1),
+    }
+}
+
+/// Viewport that places the screen pixel at the origin of a single pixel framebuffer, so that
+/// the whole screen is drawn with its projection and only that pixel is kept.
+fn selection_viewport(screen_position: [i32; 2], (width, height): (u32, u32)) -> [i32; 4] {
+    // Screen rows go down from the top, and viewport ones up from the bottom
+    [
+        -screen_position[0],
+        screen_position[1] + 1 - height as i32,
+        width as i32,
+        height as i32,
+    ]
+}
+
 impl<G, S: Symbol<G>> Drop for StaticLayer<G, S> {
     fn drop(&mut self) {
         self.clean();
@@ -244,3 +494,37 @@ impl DrawingMode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::CameraMode;
+
+    #[test]
+    fn selection_pixel_in_perspective() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(800, 600);
+        position.set_camera_mode(CameraMode::perspective());
+        position.set_center(1000.0, -500.0);
+        position.set_resolution(2.5);
+        position.set_rotation(1.0, 0.7);
+
+        for pixel in [[0, 0], [123, 456], [700, 40], [799, 599]] {
+            let point = position
+                .screen_to_map([pixel[0] as f32 + 0.5, pixel[1] as f32 + 0.5])
+                .unwrap();
+            let clip = position.matrix_relative_to([point[0], point[1], 0.0])
+                * na::Vector4::new(0.0, 0.0, 0.0, 1.0);
+            let [x, y, width, height] = selection_viewport(pixel, (800, 600));
+            let framebuffer_x = x as f32 + (clip.x / clip.w + 1.0) / 2.0 * width as f32;
+            let framebuffer_y = y as f32 + (clip.y / clip.w + 1.0) / 2.0 * height as f32;
+            assert!(
+                (0.0..1.0).contains(&framebuffer_x) && (0.0..1.0).contains(&framebuffer_y),
+                "{:?}: {} {}",
+                pixel,
+                framebuffer_x,
+                framebuffer_y
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+/// Range of map resolutions at which a layer is displayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolutionRange {
+    /// The layer is hidden when the map is zoomed in further than this resolution.
+    pub min: Option<f32>,
+    /// The layer is hidden when the map is zoomed out further than this resolution.
+    pub max: Option<f32>,
+    /// If bigger than 1, the layer fades in and out inside the range: its opacity changes from
+    /// 0 at `min` to 1 at `min * fade`, and from 1 at `max / fade` to 0 at `max`.
+    pub fade: f32,
+}
+
+impl Default for ResolutionRange {
+    fn default() -> Self {
+        Self {
+            min: None,
+            max: None,
+            fade: 1.0,
+        }
+    }
+}
+
+impl ResolutionRange {
+    pub fn new(min: Option<f32>, max: Option<f32>) -> Self {
+        Self {
+            min,
+            max,
+            fade: 1.0,
+        }
+    }
+
+    pub fn with_fade(mut self, fade: f32) -> Self {
+        self.fade = fade.max(1.0);
+        self
+    }
+
+    // `Option::is_none_or` needs Rust 1.82.
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn contains(&self, resolution: f32) -> bool {
+        self.min.map_or(true, |min| resolution >= min)
+            && self.max.map_or(true, |max| resolution <= max)
+    }
+
+    /// Returns the opacity multiplier of the layer at the given resolution: 0 outside of the
+    /// range, 1 inside of it, and a value in between in the fading zones.
+    pub fn opacity_at(&self, resolution: f32) -> f32 {
+        if !self.contains(resolution) {
+            return 0.0;
+        }
+
+        if self.fade <= 1.0 {
+            return 1.0;
+        }
+
+        // Fading is linear in the logarithmic scale, the same way zoom levels change
+        let fade = self.fade.ln();
+        let from_min = self.min.map_or(1.0, |min| (resolution / min).ln() / fade);
+        let from_max = self.max.map_or(1.0, |max| (max / resolution).ln() / fade);
+
+        from_min.min(from_max).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opacity_in_range() {
+        let range = ResolutionRange::new(Some(1.0), Some(100.0));
+        assert_eq!(0.0, range.opacity_at(0.5));
+        assert_eq!(1.0, range.opacity_at(1.0));
+        assert_eq!(1.0, range.opacity_at(100.0));
+        assert_eq!(0.0, range.opacity_at(101.0));
+        assert_eq!(1.0, ResolutionRange::default().opacity_at(1e9));
+    }
+
+    #[test]
+    fn opacity_fades_at_boundaries() {
+        let range = ResolutionRange::new(Some(1.0), Some(100.0)).with_fade(4.0);
+        assert_eq!(0.0, range.opacity_at(1.0));
+        assert!((range.opacity_at(2.0) - 0.5).abs() < 1e-6);
+        assert_eq!(1.0, range.opacity_at(10.0));
+        assert!((range.opacity_at(50.0) - 0.5).abs() < 1e-6);
+        assert_eq!(0.0, range.opacity_at(100.0));
+    }
+}
@@ -1,8 +1,12 @@
 #![deny(clippy::suspicious, clippy::style, clippy::complexity, clippy::perf)]
 
 pub mod control;
+pub mod crs;
 pub mod event;
+pub mod feature;
+pub mod geometry;
 pub mod gl;
+pub mod io;
 pub mod layer;
 pub mod map;
 pub mod render_target;
@@ -11,10 +15,17 @@ pub mod symbol;
 
 extern crate nalgebra as na;
 
-pub type Point = [f32; 2];
+/// Point in map coordinates. Map coordinates are stored with double precision, so that the data
+/// in projected coordinate systems with big coordinate values can be displayed up to a very
+/// small scale. Coordinates are converted to single precision only relative to some origin
+/// close to the geometry when the data is sent to the GPU.
+pub type Point = [f64; 2];
 pub type Color = [f32; 4];
-pub type Point3 = [f32; 3];
+pub type Point3 = [f64; 3];
 
 pub type Polyline = Vec<Point3>;
 pub type Polygon = Vec<Vec<Point3>>;
 pub type PolygonRef = [Vec<Point3>];
+
+/// Axis aligned rectangle `[x_min, y_min, x_max, y_max]`.
+pub type BoundingBox = [f64; 4];
@@ -0,0 +1,254 @@
+use crate::map::fly_to::{FlyToCurve, FlyToOptions};
+use crate::map::MapPosition;
+use instant::Instant;
+
+/// Easing function applied to the linear progress of an animation.
+#[derive(Clone, Copy, Default)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    #[default]
+    EaseInOut,
+    Custom(fn(f32) -> f32),
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::Custom(f) => f(t),
+        }
+    }
+}
+
+impl std::fmt::Debug for Easing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Easing::Linear => write!(f, "Linear"),
+            Easing::EaseIn => write!(f, "EaseIn"),
+            Easing::EaseOut => write!(f, "EaseOut"),
+            Easing::EaseInOut => write!(f, "EaseInOut"),
+            Easing::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+/// Transition of the map view to the target position.
+///
+/// The starting position is captured when the animation actually starts, so animations queued
+/// after another one continue from wherever the previous one has stopped.
+#[derive(Debug)]
+pub struct MapAnimation {
+    from: Option<MapPosition>,
+    to: MapPosition,
+    duration: Option<u64>,
+    start: Option<Instant>,
+    easing: Easing,
+    transition: Transition,
+}
+
+#[derive(Debug)]
+enum Transition {
+    Linear,
+    FlyTo {
+        options: FlyToOptions,
+        curve: Option<FlyToCurve>,
+    },
+}
+
+impl MapAnimation {
+    pub fn new(to: MapPosition, duration: u64, easing: Easing) -> Self {
+        Self {
+            from: None,
+            to,
+            duration: Some(duration),
+            start: None,
+            easing,
+            transition: Transition::Linear,
+        }
+    }
+
+    /// Creates the animation that zooms out, moves the map and then zooms in to the target.
+    pub fn fly_to(to: MapPosition, options: FlyToOptions) -> Self {
+        Self {
+            from: None,
+            to,
+            duration: options.duration,
+            start: None,
+            easing: options.easing,
+            transition: Transition::FlyTo {
+                options,
+                curve: None,
+            },
+        }
+    }
+
+    pub fn target(&self) -> &MapPosition {
+        &self.to
+    }
+
+    /// Duration of the animation in milliseconds. For fly-to animations without explicitly set
+    /// duration it is unknown until the animation is started.
+    pub fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.start.is_some()
+    }
+
+    pub(crate) fn start(&mut self, position: &MapPosition, now: Instant) {
+        if let Transition::FlyTo { options, curve } = &mut self.transition {
+            let fly_to_curve = FlyToCurve::new(position, &self.to, options.curve);
+            if self.duration.is_none() {
+                let duration = fly_to_curve.length() / options.speed as f64 * 1000.0;
+                self.duration = Some(if duration.is_finite() {
+                    duration as u64
+                } else {
+                    0
+                });
+            }
+
+            *curve = Some(fly_to_curve);
+        }
+
+        self.from = Some(position.clone());
+        self.start = Some(now);
+    }
+
+    /// Applies the state of the animation at the moment `now` to the `position`. Returns `true`
+    /// if the animation is finished.
+    pub(crate) fn apply(&self, position: &mut MapPosition, now: Instant) -> bool {
+        let (from, start) = match (&self.from, self.start) {
+            (Some(from), Some(start)) => (from, start),
+            _ => return false,
+        };
+
+        let elapsed = if now > start {
+            (now - start).as_millis() as f32
+        } else {
+            0.0
+        };
+        let progress = match self.duration {
+            Some(duration) if duration > 0 => (elapsed / duration as f32).min(1.0),
+            _ => 1.0,
+        };
+
+        let t = self.easing.apply(progress);
+        match &self.transition {
+            Transition::FlyTo {
+                curve: Some(curve), ..
+            } => curve.apply(from, &self.to, t, position),
+            _ => interpolate(from, &self.to, t, position),
+        }
+
+        progress >= 1.0
+    }
+}
+
+/// Sets the center, resolution and rotation of the `target` to the state between `from` and `to`
+/// at the point `t`. Screen size of the `target` is not changed.
+pub fn interpolate(from: &MapPosition, to: &MapPosition, t: f32, target: &mut MapPosition) {
+    let c0 = from.center();
+    let c1 = to.center();
+    let t_center = t as f64;
+    target.set_center(
+        c0[0] + (c1[0] - c0[0]) * t_center,
+        c0[1] + (c1[1] - c0[1]) * t_center,
+    );
+
+    // Resolution is interpolated in log scale, so every zoom level takes the same time
+    let r0 = from.resolution();
+    let r1 = to.resolution();
+    target.set_resolution(r0 * (r1 / r0).powf(t));
+
+    let rotation_x = from.rotation_x() + (to.rotation_x() - from.rotation_x()) * t;
+    let rotation_z = from.rotation_z() + shortest_angle(from.rotation_z(), to.rotation_z()) * t;
+    target.set_rotation(rotation_x, rotation_z);
+}
+
+pub(crate) fn shortest_angle(from: f32, to: f32) -> f32 {
+    use std::f32::consts::PI;
+
+    let diff = (to - from) % (2.0 * PI);
+    if diff > PI {
+        diff - 2.0 * PI
+    } else if diff < -PI {
+        diff + 2.0 * PI
+    } else {
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(x: f64, y: f64, resolution: f32) -> MapPosition {
+        let mut position = MapPosition::default();
+        position.set_center(x, y);
+        position.set_resolution(resolution);
+        position
+    }
+
+    #[test]
+    fn easing_bounds() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(0.0, easing.apply(0.0));
+            assert_eq!(1.0, easing.apply(1.0));
+            assert_eq!(1.0, easing.apply(2.0));
+        }
+    }
+
+    #[test]
+    fn interpolate_position() {
+        let from = position(0.0, 0.0, 1.0);
+        let to = position(100.0, -100.0, 100.0);
+        let mut target = MapPosition::default();
+
+        interpolate(&from, &to, 0.5, &mut target);
+        assert_eq!([50.0, -50.0], target.center());
+        assert!((target.resolution() - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rotation_takes_shortest_way() {
+        let mut from = MapPosition::default();
+        from.set_rotation(0.0, 0.1);
+        let mut to = MapPosition::default();
+        to.set_rotation(0.0, 2.0 * std::f32::consts::PI - 0.1);
+        let mut target = MapPosition::default();
+
+        interpolate(&from, &to, 0.5, &mut target);
+        assert!(target.rotation_z().abs() < 1e-4);
+    }
+
+    #[test]
+    fn animation_finishes_after_duration() {
+        let start = Instant::now();
+        let mut current = position(0.0, 0.0, 1.0);
+        let mut animation = MapAnimation::new(position(10.0, 10.0, 1.0), 100, Easing::Linear);
+        animation.start(&current, start);
+
+        assert!(!animation.apply(&mut current, start + instant::Duration::from_millis(50)));
+        assert_eq!([5.0, 5.0], current.center());
+        assert!(animation.apply(&mut current, start + instant::Duration::from_millis(150)));
+        assert_eq!([10.0, 10.0], current.center());
+    }
+}
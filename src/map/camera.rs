@@ -0,0 +1,73 @@
+/// Default field of view of the perspective camera, about 37 degrees.
+pub const DEFAULT_FIELD_OF_VIEW: f32 = 0.6435;
+
+/// The way the map is projected onto the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CameraMode {
+    /// Parallel projection: all the objects have the same scale independently of the distance
+    /// from the camera. Tilting the map shortens it along the vertical screen axis.
+    #[default]
+    Orthographic,
+    /// Perspective projection with the given vertical field of view in radians. Far objects look
+    /// smaller than near ones, and `z` coordinates of the geometries are displayed as real height.
+    Perspective { field_of_view: f32 },
+}
+
+impl CameraMode {
+    pub fn perspective() -> Self {
+        CameraMode::Perspective {
+            field_of_view: DEFAULT_FIELD_OF_VIEW,
+        }
+    }
+}
+
+/// Projection matrix transforming the camera space coordinates (in pixels, with the map center
+/// at the origin) into the clip space.
+pub(crate) fn projection_matrix(
+    mode: CameraMode,
+    width: f32,
+    height: f32,
+    tilt: f32,
+) -> na::Matrix4<f32> {
+    match mode {
+        CameraMode::Orthographic => {
+            // Depth is not used for rendering, but objects outside of [-1; 1] are clipped, so
+            // the depth range must include tilted ground and objects above it.
+            let depth = 4.0 * width.max(height);
+            na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(
+                2.0 / width,
+                2.0 / height,
+                1.0 / depth,
+            ))
+        }
+        CameraMode::Perspective { field_of_view } => {
+            let distance = camera_distance(field_of_view, height);
+            let (near, far) = clip_planes(field_of_view, distance, tilt);
+            na::Perspective3::new(width / height, field_of_view, near, far).to_homogeneous()
+                * na::Matrix4::new_translation(&na::Vector3::new(0.0, 0.0, -distance))
+        }
+    }
+}
+
+/// Distance from the camera to the map center in pixels, at which the map scale at the center
+/// of the screen is the same as with orthographic projection.
+pub(crate) fn camera_distance(field_of_view: f32, height: f32) -> f32 {
+    height / 2.0 / (field_of_view / 2.0).tan()
+}
+
+fn clip_planes(field_of_view: f32, distance: f32, tilt: f32) -> (f32, f32) {
+    const MAX_DISTANCE_FACTOR: f32 = 100.0;
+    let max_far = distance * MAX_DISTANCE_FACTOR;
+
+    // The ray through the top edge of the screen is the longest one. If it goes above the
+    // horizon, it never reaches the ground, so the far plane is limited by the max distance.
+    let height = distance * tilt.cos();
+    let top_ray_angle = tilt + field_of_view / 2.0;
+    let far = if top_ray_angle < std::f32::consts::FRAC_PI_2 {
+        (height / top_ray_angle.cos() * 1.01).min(max_far)
+    } else {
+        max_far
+    };
+
+    (distance / MAX_DISTANCE_FACTOR, far.max(distance * 1.01))
+}
@@ -0,0 +1,188 @@
+use crate::BoundingBox;
+
+/// Limits of the map navigation.
+#[derive(Debug, Clone)]
+pub struct ViewConstraints {
+    /// The smallest allowed resolution (the most zoomed in state).
+    pub min_resolution: Option<f32>,
+    /// The biggest allowed resolution (the most zoomed out state).
+    pub max_resolution: Option<f32>,
+    /// Area the map view cannot leave.
+    pub max_extent: Option<BoundingBox>,
+    pub extent_mode: ExtentMode,
+    /// If set, zooming by user changes the resolution only between these values.
+    pub zoom_levels: Option<Vec<f32>>,
+    pub rotation_enabled: bool,
+    pub tilt_enabled: bool,
+}
+
+/// Defines what part of the view must stay inside the `max_extent`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtentMode {
+    Center,
+    Viewport,
+}
+
+impl Default for ViewConstraints {
+    fn default() -> Self {
+        Self {
+            min_resolution: None,
+            max_resolution: None,
+            max_extent: None,
+            extent_mode: ExtentMode::Center,
+            zoom_levels: None,
+            rotation_enabled: true,
+            tilt_enabled: true,
+        }
+    }
+}
+
+impl ViewConstraints {
+    pub fn with_resolution_range(mut self, min: f32, max: f32) -> Self {
+        self.min_resolution = Some(min);
+        self.max_resolution = Some(max);
+        self
+    }
+
+    pub fn with_max_extent(mut self, extent: BoundingBox, mode: ExtentMode) -> Self {
+        self.max_extent = Some(extent);
+        self.extent_mode = mode;
+        self
+    }
+
+    /// Sets the resolutions of the zoom levels. Resolutions that are not finite positive
+    /// numbers are ignored.
+    pub fn with_zoom_levels(mut self, mut resolutions: Vec<f32>) -> Self {
+        resolutions.retain(|resolution| resolution.is_finite() && *resolution > 0.0);
+        resolutions.sort_by(f32::total_cmp);
+        resolutions.dedup();
+        self.zoom_levels = Some(resolutions);
+        self
+    }
+
+    pub fn with_rotation(mut self, enabled: bool) -> Self {
+        self.rotation_enabled = enabled;
+        self
+    }
+
+    pub fn with_tilt(mut self, enabled: bool) -> Self {
+        self.tilt_enabled = enabled;
+        self
+    }
+
+    pub fn clamp_resolution(&self, resolution: f32) -> f32 {
+        let mut resolution = resolution;
+        if let Some(max) = self.max_resolution {
+            resolution = resolution.min(max);
+        }
+        if let Some(min) = self.min_resolution {
+            resolution = resolution.max(min);
+        }
+
+        resolution
+    }
+
+    /// Returns the resolution the map should have after zooming from the `current` resolution
+    /// to the `target` one.
+    pub fn zoom_resolution(&self, current: f32, target: f32) -> f32 {
+        const EPSILON: f32 = 1e-4;
+
+        let target = match &self.zoom_levels {
+            Some(levels) if !levels.is_empty() => {
+                let level = if target < current {
+                    levels
+                        .iter()
+                        .rev()
+                        .find(|level| **level < current * (1.0 - EPSILON))
+                } else if target > current {
+                    levels
+                        .iter()
+                        .find(|level| **level > current * (1.0 + EPSILON))
+                } else {
+                    None
+                };
+
+                *level.unwrap_or(&current)
+            }
+            _ => target,
+        };
+
+        self.clamp_resolution(target)
+    }
+
+    /// Moves the center so that it is inside the `max_extent`. `half_size` is the half of the
+    /// visible area size in map units, and is used only in the `ExtentMode::Viewport` mode.
+    pub fn clamp_center(&self, center: [f64; 2], half_size: [f64; 2]) -> [f64; 2] {
+        let extent = match self.max_extent {
+            Some(extent) => extent,
+            None => return center,
+        };
+
+        let half_size = match self.extent_mode {
+            ExtentMode::Center => [0.0, 0.0],
+            ExtentMode::Viewport => half_size,
+        };
+
+        let clamp = |value: f64, min: f64, max: f64, half_size: f64| {
+            if max - min <= 2.0 * half_size {
+                (min + max) / 2.0
+            } else {
+                value.clamp(min + half_size, max - half_size)
+            }
+        };
+
+        [
+            clamp(center[0], extent[0], extent[2], half_size[0]),
+            clamp(center[1], extent[1], extent[3], half_size[1]),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_snaps_to_levels() {
+        let constraints = ViewConstraints::default().with_zoom_levels(vec![1.0, 4.0, 2.0, 8.0]);
+        assert_eq!(2.0, constraints.zoom_resolution(4.0, 3.9));
+        assert_eq!(8.0, constraints.zoom_resolution(4.0, 4.1));
+        assert_eq!(2.0, constraints.zoom_resolution(3.0, 2.9));
+        assert_eq!(1.0, constraints.zoom_resolution(1.0, 0.5));
+    }
+
+    #[test]
+    fn invalid_zoom_levels_are_ignored() {
+        let constraints = ViewConstraints::default().with_zoom_levels(vec![
+            4.0,
+            f32::NAN,
+            1.0,
+            f32::INFINITY,
+            -2.0,
+            0.0,
+            4.0,
+        ]);
+        assert_eq!(Some(vec![1.0, 4.0]), constraints.zoom_levels);
+    }
+
+    #[test]
+    fn center_stays_in_extent() {
+        let constraints = ViewConstraints::default()
+            .with_max_extent([0.0, 0.0, 100.0, 100.0], ExtentMode::Center);
+        assert_eq!(
+            [100.0, 0.0],
+            constraints.clamp_center([150.0, -1.0], [10.0, 10.0])
+        );
+
+        let constraints = ViewConstraints::default()
+            .with_max_extent([0.0, 0.0, 100.0, 100.0], ExtentMode::Viewport);
+        assert_eq!(
+            [90.0, 10.0],
+            constraints.clamp_center([150.0, -1.0], [10.0, 10.0])
+        );
+        assert_eq!(
+            [50.0, 10.0],
+            constraints.clamp_center([150.0, -1.0], [60.0, 10.0])
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use crate::map::animation::shortest_angle;
+use crate::map::{Easing, MapPosition};
+
+/// Settings of the fly-to transition.
+#[derive(Debug, Clone, Copy)]
+pub struct FlyToOptions {
+    /// Average speed of the animation in screens per second. Not used if `duration` is set.
+    pub speed: f32,
+    /// Defines how much the map zooms out during the transition. The bigger the value, the more
+    /// the map is zoomed out. `1.42` gives the path found optimal by user studies.
+    pub curve: f32,
+    /// Duration of the animation in milliseconds. If not set, it is calculated from the `speed`
+    /// and length of the path.
+    pub duration: Option<u64>,
+    pub easing: Easing,
+}
+
+impl Default for FlyToOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.2,
+            curve: 1.42,
+            duration: None,
+            easing: Easing::EaseInOut,
+        }
+    }
+}
+
+/// Optimal zoom-and-pan path between two map positions, as described by J. van Wijk and
+/// W. Nuij in "Smooth and efficient zooming and panning".
+#[derive(Debug, Clone)]
+pub struct FlyToCurve {
+    rho: f64,
+    r0: f64,
+    w0: f64,
+    u1: f64,
+    length: f64,
+    direction: f64,
+    is_zoom_only: bool,
+}
+
+impl FlyToCurve {
+    pub fn new(from: &MapPosition, to: &MapPosition, curve: f32) -> Self {
+        let screen_size = from.width_px().max(from.height_px()) as f64;
+        let w0 = screen_size * from.resolution() as f64;
+        let w1 = screen_size * to.resolution() as f64;
+
+        let c0 = from.center();
+        let c1 = to.center();
+        let u1 = ((c1[0] - c0[0]).powi(2) + (c1[1] - c0[1]).powi(2)).sqrt();
+
+        let rho = curve as f64;
+        let rho2 = rho * rho;
+        let r = |i: bool| {
+            let (w, sign) = if i { (w1, -1.0) } else { (w0, 1.0) };
+            let b = (w1 * w1 - w0 * w0 + sign * rho2 * rho2 * u1 * u1) / (2.0 * w * rho2 * u1);
+            // same as ln(sqrt(b^2 + 1) - b), but without catastrophic cancellation for large b
+            -b.asinh()
+        };
+
+        let r0 = r(false);
+        let length = (r(true) - r0) / rho;
+
+        if u1.abs() < f64::EPSILON * w0.max(w1) || !length.is_finite() {
+            Self {
+                rho,
+                r0: 0.0,
+                w0,
+                u1,
+                length: (w1 / w0).ln().abs() / rho,
+                direction: if w1 < w0 { -1.0 } else { 1.0 },
+                is_zoom_only: true,
+            }
+        } else {
+            Self {
+                rho,
+                r0,
+                w0,
+                u1,
+                length,
+                direction: 1.0,
+                is_zoom_only: false,
+            }
+        }
+    }
+
+    /// Length of the path in units of screen size.
+    pub fn length(&self) -> f64 {
+        self.length
+    }
+
+    /// Returns the part of the distance between the centers and the resolution multiplier at the
+    /// point `t` of the path.
+    pub fn at(&self, t: f32) -> (f64, f64) {
+        let t = t as f64;
+        let s = t * self.length;
+        if self.is_zoom_only {
+            return (t, (self.direction * self.rho * s).exp());
+        }
+
+        let rho_s = self.r0 + self.rho * s;
+        let w = self.r0.cosh() / rho_s.cosh();
+        let u = self.w0 * (self.r0.cosh() * rho_s.tanh() - self.r0.sinh())
+            / (self.rho * self.rho)
+            / self.u1;
+        (u, w)
+    }
+
+    /// Sets the center, resolution and rotation of the `target` to the state at the point `t`
+    /// of the path.
+    pub fn apply(&self, from: &MapPosition, to: &MapPosition, t: f32, target: &mut MapPosition) {
+        let (u, w) = if t >= 1.0 { (1.0, 1.0) } else { self.at(t) };
+        let c0 = from.center();
+        let c1 = to.center();
+        target.set_center(c0[0] + (c1[0] - c0[0]) * u, c0[1] + (c1[1] - c0[1]) * u);
+
+        let resolution = if t >= 1.0 {
+            to.resolution()
+        } else {
+            from.resolution() * w as f32
+        };
+        target.set_resolution(resolution);
+
+        let rotation_x = from.rotation_x() + (to.rotation_x() - from.rotation_x()) * t;
+        let rotation_z = from.rotation_z() + shortest_angle(from.rotation_z(), to.rotation_z()) * t;
+        target.set_rotation(rotation_x, rotation_z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(x: f64, y: f64, resolution: f32) -> MapPosition {
+        let mut position = MapPosition::default();
+        position.set_screen_size(800, 600);
+        position.set_center(x, y);
+        position.set_resolution(resolution);
+        position
+    }
+
+    #[test]
+    fn curve_ends_at_target() {
+        let from = position(0.0, 0.0, 10.0);
+        let to = position(100_000.0, 50_000.0, 5.0);
+        let curve = FlyToCurve::new(&from, &to, 1.42);
+
+        let mut target = from.clone();
+        curve.apply(&from, &to, 1.0, &mut target);
+        assert!((target.center()[0] - 100_000.0).abs() < 1.0);
+        assert!((target.center()[1] - 50_000.0).abs() < 1.0);
+        assert_eq!(5.0, target.resolution());
+    }
+
+    #[test]
+    fn curve_zooms_out_in_the_middle() {
+        let from = position(0.0, 0.0, 10.0);
+        let to = position(1_000_000.0, 0.0, 10.0);
+        let curve = FlyToCurve::new(&from, &to, 1.42);
+
+        let mut target = from.clone();
+        curve.apply(&from, &to, 0.5, &mut target);
+        assert!(target.resolution() > 100.0);
+        assert!((target.center()[0] - 500_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn zoom_only_curve() {
+        let from = position(0.0, 0.0, 10.0);
+        let to = position(0.0, 0.0, 1.0);
+        let curve = FlyToCurve::new(&from, &to, 1.42);
+
+        let mut target = from.clone();
+        curve.apply(&from, &to, 0.5, &mut target);
+        assert_eq!([0.0, 0.0], target.center());
+        assert!((target.resolution() - 10f32.sqrt()).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::map::ViewState;
+use std::collections::VecDeque;
+
+const DEFAULT_CAPACITY: usize = 50;
+
+/// Bounded list of the map views the user navigated through, with a pointer to the current one.
+///
+/// Adding a view when the pointer is not at the end of the list removes all the views after
+/// the pointer, the same way as browser history works.
+#[derive(Debug, Clone)]
+pub struct ViewHistory {
+    entries: VecDeque<ViewState>,
+    current: usize,
+    capacity: usize,
+}
+
+impl Default for ViewHistory {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl ViewHistory {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            current: 0,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Adds the view after the current one and makes it current. Nothing is done if the view is
+    /// the same as the current one.
+    pub fn push(&mut self, state: ViewState) {
+        if self.current() == Some(&state) {
+            return;
+        }
+
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.current + 1);
+        }
+
+        self.entries.push_back(state);
+        if self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.current = self.entries.len() - 1;
+    }
+
+    /// Replaces the current view without adding a new entry. Used to merge a series of small
+    /// changes, like zooming with the mouse wheel, into one history step.
+    pub fn replace_current(&mut self, state: ViewState) {
+        if self.entries.is_empty() {
+            self.push(state);
+        } else {
+            self.entries.truncate(self.current + 1);
+            self.entries[self.current] = state;
+        }
+    }
+
+    pub fn current(&self) -> Option<&ViewState> {
+        self.entries.get(self.current)
+    }
+
+    /// Moves the pointer one step back and returns the view at the new position.
+    pub fn back(&mut self) -> Option<&ViewState> {
+        if !self.can_go_back() {
+            return None;
+        }
+
+        self.current -= 1;
+        self.current()
+    }
+
+    /// Moves the pointer one step forward and returns the view at the new position.
+    pub fn forward(&mut self) -> Option<&ViewState> {
+        if !self.can_go_forward() {
+            return None;
+        }
+
+        self.current += 1;
+        self.current()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.current > 0
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.current + 1 < self.entries.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.current = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(x: f64) -> ViewState {
+        ViewState::new([x, 0.0], 1.0)
+    }
+
+    #[test]
+    fn back_and_forward() {
+        let mut history = ViewHistory::default();
+        history.push(state(1.0));
+        history.push(state(2.0));
+        history.push(state(2.0));
+        history.push(state(3.0));
+        assert_eq!(3, history.len());
+
+        assert_eq!(Some(&state(2.0)), history.back());
+        assert_eq!(Some(&state(1.0)), history.back());
+        assert_eq!(None, history.back());
+        assert_eq!(Some(&state(2.0)), history.forward());
+
+        history.push(state(4.0));
+        assert!(!history.can_go_forward());
+        assert_eq!(Some(&state(2.0)), history.back());
+        assert_eq!(Some(&state(1.0)), history.back());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = ViewHistory::with_capacity(3);
+        for i in 0..5 {
+            history.push(state(i as f64));
+        }
+
+        assert_eq!(3, history.len());
+        assert_eq!(Some(&state(3.0)), history.back());
+        assert_eq!(Some(&state(2.0)), history.back());
+        assert!(!history.can_go_back());
+    }
+}
@@ -0,0 +1,1229 @@
+mod animation;
+pub use animation::{interpolate, Easing, MapAnimation};
+
+mod camera;
+pub use camera::{CameraMode, DEFAULT_FIELD_OF_VIEW};
+
+mod constraints;
+pub use constraints::{ExtentMode, ViewConstraints};
+
+mod fly_to;
+pub use fly_to::{FlyToCurve, FlyToOptions};
+
+mod history;
+pub use history::ViewHistory;
+
+mod view_state;
+pub use view_state::{ParseViewStateError, ViewState};
+
+use crate::control::{ControlState, MapControlSettings, MapEventDispatcher};
+use crate::crs::Crs;
+use crate::event::{EventListener, HandlerStore, TypedHandlerStore};
+use crate::geometry::{bbox_intersects, expand_bbox, merge_bbox};
+use crate::layer::{Layer, LayerId, LayerList};
+use crate::render_target::RenderTarget;
+use crate::{BoundingBox, Point, Point3};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+
+pub struct Map {
+    layers: LayerList,
+    position: MapPosition,
+    animations: VecDeque<MapAnimation>,
+    history: ViewHistory,
+    control_state: ControlState,
+    handler_store: Rc<RefCell<HandlerStore>>,
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Self {
+            layers: LayerList::new(),
+            position: MapPosition::default(),
+            animations: VecDeque::new(),
+            history: ViewHistory::default(),
+            control_state: ControlState::default(),
+            handler_store: Rc::new(RefCell::new(HandlerStore::default())),
+        }
+    }
+
+    pub fn draw(&mut self, target: &mut RenderTarget) {
+        let (x, y) = target.get_dimensions();
+        self.position.set_screen_size(x, y);
+
+        let resolution = self.position.resolution();
+        for entry in self.layers.iter() {
+            let opacity = entry.opacity_at(resolution);
+            if opacity > 0.0 {
+                entry
+                    .layer()
+                    .borrow_mut()
+                    .draw(target, &self.position, opacity);
+            }
+        }
+    }
+
+    /// Starts animated transition from the current position to the given one. Running and
+    /// queued animations are cancelled.
+    pub fn animate_to(&mut self, position: MapPosition, duration: u64) {
+        self.animate_to_with_easing(position, duration, Easing::default());
+    }
+
+    pub fn animate_to_with_easing(&mut self, position: MapPosition, duration: u64, easing: Easing) {
+        self.cancel_animation();
+        self.queue_animation(position, duration, easing);
+    }
+
+    /// Animates the map to the given center and resolution, zooming out in the middle of the
+    /// way, so that both start and end points are visible during the transition. Rotation of
+    /// the map is preserved.
+    pub fn fly_to(&mut self, center: Point, resolution: f32, options: FlyToOptions) {
+        let mut target = self.position.clone();
+        target.set_center(center[0], center[1]);
+        target.set_resolution(resolution);
+
+        self.cancel_animation();
+        self.animations
+            .push_back(MapAnimation::fly_to(target, options));
+    }
+
+    /// Adds the animation to the queue. It will be started after all the previously added
+    /// animations are finished.
+    pub fn queue_animation(&mut self, position: MapPosition, duration: u64, easing: Easing) {
+        self.animations
+            .push_back(MapAnimation::new(position, duration, easing));
+    }
+
+    /// Stops running animation and removes all queued ones. The map stays at the position
+    /// where the animation was stopped.
+    pub fn cancel_animation(&mut self) {
+        self.animations.clear();
+    }
+
+    pub fn is_animating(&self) -> bool {
+        !self.animations.is_empty()
+    }
+
+    /// Adds the layer on top of the others. The returned id can be used to change the
+    /// visibility, opacity and order of the layer through `layers_mut`.
+    pub fn add_layer(&mut self, layer: Rc<RefCell<dyn Layer>>) -> LayerId {
+        self.layers.add(layer)
+    }
+
+    /// Inserts the layer at the given position in the drawing order, 0 being the bottom.
+    pub fn insert_layer(&mut self, index: usize, layer: Rc<RefCell<dyn Layer>>) -> LayerId {
+        self.layers.insert(index, layer)
+    }
+
+    pub fn remove_layer(&mut self, id: LayerId) -> Option<Rc<RefCell<dyn Layer>>> {
+        self.layers.remove(id)
+    }
+
+    pub fn layers(&self) -> &LayerList {
+        &self.layers
+    }
+
+    pub fn layers_mut(&mut self) -> &mut LayerList {
+        &mut self.layers
+    }
+
+    pub(crate) fn animation_frame(&mut self) {
+        self.animation_frame_at(instant::Instant::now());
+    }
+
+    fn animation_frame_at(&mut self, now: instant::Instant) {
+        while let Some(animation) = self.animations.front_mut() {
+            if !animation.is_started() {
+                animation.start(&self.position, now);
+                self.history.push(self.position.view_state());
+            }
+
+            if !animation.apply(&mut self.position, now) {
+                return;
+            }
+
+            self.animations.pop_front();
+            self.record_view();
+        }
+    }
+
+    /// Adds the current view to the navigation history. The map control calls it before and
+    /// after every user navigation; call it when the view is changed programmatically.
+    pub fn record_view(&mut self) {
+        self.history.push(self.position.view_state());
+    }
+
+    pub fn history(&self) -> &ViewHistory {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut ViewHistory {
+        &mut self.history
+    }
+
+    /// Returns the map to the previous view in the navigation history. Returns `false` if there
+    /// is no previous view.
+    pub fn back(&mut self) -> bool {
+        self.record_view();
+        match self.history.back() {
+            Some(state) => {
+                let state = *state;
+                self.cancel_animation();
+                self.position.set_view_state(&state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes to the next view in the navigation history, undoing `back`. Returns `false` if
+    /// there is no next view.
+    pub fn forward(&mut self) -> bool {
+        match self.history.forward() {
+            Some(state) => {
+                let state = *state;
+                self.cancel_animation();
+                self.position.set_view_state(&state);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn position(&self) -> &MapPosition {
+        &self.position
+    }
+
+    pub fn position_mut(&mut self) -> &mut MapPosition {
+        &mut self.position
+    }
+
+    pub fn set_center(&mut self, x: f64, y: f64) {
+        self.position.set_center(x, y);
+    }
+
+    pub fn set_resolution(&mut self, resolution: f32) {
+        self.position.set_resolution(resolution);
+    }
+
+    pub fn constraints(&self) -> &ViewConstraints {
+        self.position.constraints()
+    }
+
+    pub fn set_constraints(&mut self, constraints: ViewConstraints) {
+        self.position.set_constraints(constraints);
+    }
+
+    pub fn crs(&self) -> &Crs {
+        self.position.crs()
+    }
+
+    /// Sets the CRS of the map. Layers with a CRS set are reprojected into it on the next draw.
+    pub fn set_crs(&mut self, crs: Crs) {
+        self.position.set_crs(crs);
+    }
+
+    /// Changes the map position to show the whole `bbox` with the given padding in pixels.
+    pub fn fit_bounds(&mut self, bbox: BoundingBox, padding_px: f32) {
+        self.position.fit_bounds(bbox, padding_px);
+    }
+
+    /// Changes the map position to show all the features of the layer. Does nothing if the
+    /// extent of the layer is unknown.
+    pub fn fit_layer(&mut self, layer: &dyn Layer, padding_px: f32) {
+        if let Some(bbox) = self.layer_extent(layer) {
+            self.fit_bounds(bbox, padding_px);
+        }
+    }
+
+    /// Combined extent of all layers of the map in the map CRS.
+    pub fn layers_extent(&self) -> Option<BoundingBox> {
+        self.layers.iter().fold(None, |acc, entry| {
+            merge_bbox(acc, self.layer_extent(&*entry.layer().borrow()))
+        })
+    }
+
+    fn layer_extent(&self, layer: &dyn Layer) -> Option<BoundingBox> {
+        let extent = layer.extent()?;
+        match layer.crs() {
+            Some(crs) => Some(crs.transform_bbox(self.crs(), extent)),
+            None => Some(extent),
+        }
+    }
+
+    pub fn control(&mut self) -> MapEventDispatcher {
+        MapEventDispatcher {
+            map: self,
+            settings: MapControlSettings::default(),
+        }
+    }
+
+    pub fn control_state(&self) -> &ControlState {
+        &self.control_state
+    }
+
+    pub fn control_state_mut(&mut self) -> &mut ControlState {
+        &mut self.control_state
+    }
+}
+
+impl<E> EventListener<E> for Map
+where
+    E: Copy,
+    HandlerStore: TypedHandlerStore<E>,
+{
+    fn handler_store(&self) -> Weak<RefCell<HandlerStore>> {
+        Rc::downgrade(&self.handler_store)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MapPosition {
+    screen_scale: na::Matrix4<f32>,
+    resolution: f32,
+    center: Point,
+    rotation_x: f32,
+    rotation_z: f32,
+    constraints: ViewConstraints,
+    camera_mode: CameraMode,
+    crs: Crs,
+    wrap_world: bool,
+}
+
+impl MapPosition {
+    pub fn set_center(&mut self, x: f64, y: f64) {
+        self.center = self.constraints.clamp_center([x, y], self.half_view_size());
+    }
+
+    pub fn set_resolution(&mut self, resolution: f32) {
+        let resolution = self.constraints.clamp_resolution(resolution);
+        self.resolution = resolution;
+        self.apply_center_constraint();
+    }
+
+    pub fn constraints(&self) -> &ViewConstraints {
+        &self.constraints
+    }
+
+    /// Sets the constraints and moves the position to satisfy them.
+    pub fn set_constraints(&mut self, constraints: ViewConstraints) {
+        self.constraints = constraints;
+        self.set_resolution(self.resolution());
+    }
+
+    fn apply_center_constraint(&mut self) {
+        let center = self.center();
+        self.set_center(center[0], center[1]);
+    }
+
+    /// Half size of the bounding box of the visible area in map units.
+    fn half_view_size(&self) -> [f64; 2] {
+        let extent = self.visible_extent();
+        let center = self.center();
+        [
+            (center[0] - extent[0]).max(extent[2] - center[0]),
+            (center[1] - extent[1]).max(extent[3] - center[1]),
+        ]
+    }
+
+    pub fn camera_mode(&self) -> CameraMode {
+        self.camera_mode
+    }
+
+    pub fn set_camera_mode(&mut self, camera_mode: CameraMode) {
+        self.camera_mode = camera_mode;
+        self.apply_center_constraint();
+    }
+
+    /// Coordinate reference system of the map. Center, extents and all other coordinates of the
+    /// position are in this CRS.
+    pub fn crs(&self) -> &Crs {
+        &self.crs
+    }
+
+    /// If enabled and the map CRS covers the whole globe, copies of the world are shown to the
+    /// left and to the right of the main one, and features are unwrapped across the
+    /// antimeridian. Disabled by default.
+    pub fn set_wrap_world(&mut self, wrap_world: bool) {
+        self.wrap_world = wrap_world;
+    }
+
+    pub fn wrap_world(&self) -> bool {
+        self.wrap_world
+    }
+
+    /// Width of the world in map units, if the world is wrapped.
+    pub fn world_width(&self) -> Option<f64> {
+        if self.wrap_world {
+            self.crs.world_width()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the shifts along the x axis of the copies of the `bbox` that are visible on the
+    /// screen. If the world is not wrapped, the result is `[0.0]` if the `bbox` is visible and
+    /// empty otherwise. `margin` is added to the visible area on each side.
+    pub fn world_offsets(&self, bbox: &BoundingBox, margin: f64) -> Vec<f64> {
+        // Zoomed out map can show a lot of copies, but drawing too many of them is useless
+        const MAX_COPIES: f64 = 16.0;
+
+        let visible = expand_bbox(&self.visible_extent(), margin);
+        let width = match self.world_width() {
+            Some(width) => width,
+            None if bbox_intersects(&visible, bbox) => return vec![0.0],
+            None => return vec![],
+        };
+
+        if visible[1] > bbox[3] || bbox[1] > visible[3] {
+            return vec![];
+        }
+
+        let first = ((visible[0] - bbox[2]) / width).ceil();
+        let last = ((visible[2] - bbox[0]) / width)
+            .floor()
+            .min(first + MAX_COPIES - 1.0);
+        let mut offsets = vec![];
+        let mut copy = first;
+        while copy <= last {
+            offsets.push(copy * width);
+            copy += 1.0;
+        }
+
+        offsets
+    }
+
+    /// Changes the CRS of the map. The center is converted into the new CRS, other parameters
+    /// (including the resolution and the constraints) stay unchanged.
+    pub fn set_crs(&mut self, crs: Crs) {
+        let [x, y, _] = self
+            .crs
+            .transform(&crs, [self.center[0], self.center[1], 0.0]);
+        self.crs = crs;
+        self.set_center(x, y);
+    }
+
+    pub fn width_px(&self) -> f32 {
+        2.0 / self.screen_scale[(0, 0)]
+    }
+
+    pub fn height_px(&self) -> f32 {
+        2.0 / self.screen_scale[(1, 1)]
+    }
+
+    /// Moves the map by the given number of pixels. Positive `dy` moves the map up.
+    pub fn translate_px(&mut self, dx: i32, dy: i32) {
+        let from = [self.width_px() / 2.0, self.height_px() / 2.0];
+        let to = [from[0] + dx as f32, from[1] - dy as f32];
+        self.drag_px(from, to);
+    }
+
+    /// Moves the map so that the point of the map displayed at the `from` pixel is displayed at
+    /// the `to` pixel.
+    pub fn drag_px(&mut self, from: [f32; 2], to: [f32; 2]) {
+        // With perspective camera the screen and map movements are not proportional, so the
+        // position is refined several times to put the dragged point under the cursor.
+        const ITERATIONS: usize = 3;
+
+        let (target, _) = self.ground_point(&self.inverse_relative_transformation(), from);
+        for _ in 0..ITERATIONS {
+            let (current, _) = self.ground_point(&self.inverse_relative_transformation(), to);
+            let center = self.center();
+            self.set_center(
+                center[0] - (current[0] - target[0]),
+                center[1] - (current[1] - target[1]),
+            );
+
+            if !matches!(self.camera_mode, CameraMode::Perspective { .. }) {
+                break;
+            }
+        }
+    }
+
+    /// Rotates the map around its center so that the point of the map displayed at the `from`
+    /// pixel is displayed in the direction of the `to` pixel from the center. The angle is
+    /// measured on the ground, so the rotation follows the cursor on tilted maps as well.
+    pub fn rotate_px(&mut self, from: [f32; 2], to: [f32; 2]) {
+        let inverse = self.inverse_relative_transformation();
+        let (from, from_on_ground) = self.relative_ground_point(&inverse, from);
+        let (to, to_on_ground) = self.relative_ground_point(&inverse, to);
+        if !from_on_ground || !to_on_ground || from == [0.0, 0.0] || to == [0.0, 0.0] {
+            return;
+        }
+
+        let angle = to[1].atan2(to[0]) - from[1].atan2(from[0]);
+        self.rotate(0.0, angle);
+    }
+
+    /// Rotates the map by the given angles. Rotation around an axis is ignored if it is disabled
+    /// by the constraints.
+    pub fn rotate(&mut self, x: f32, z: f32) {
+        let x = if self.constraints.tilt_enabled {
+            x
+        } else {
+            0.0
+        };
+        let z = if self.constraints.rotation_enabled {
+            z
+        } else {
+            0.0
+        };
+        self.set_rotation(self.rotation_x + x, self.rotation_z + z);
+    }
+
+    pub fn set_rotation(&mut self, x: f32, z: f32) {
+        const MIN_X_ANGLE: f32 = 0.0;
+        const MAX_X_ANGLE: f32 = 3.0 * std::f32::consts::FRAC_PI_8;
+        self.rotation_x = x.clamp(MIN_X_ANGLE, MAX_X_ANGLE);
+        self.rotation_z = z;
+        self.apply_center_constraint();
+    }
+
+    /// Returns the center, resolution and rotation of the position.
+    pub fn view_state(&self) -> ViewState {
+        ViewState::new(self.center, self.resolution).with_rotation(self.rotation_x, self.rotation_z)
+    }
+
+    /// Sets the center, resolution and rotation of the position. The values are restricted by
+    /// the constraints the same way as when they are set one by one.
+    pub fn set_view_state(&mut self, state: &ViewState) {
+        self.set_resolution(state.resolution);
+        self.set_rotation(state.rotation_x, state.rotation_z);
+        self.set_center(state.center[0], state.center[1]);
+    }
+
+    pub fn rotation_x(&self) -> f32 {
+        self.rotation_x
+    }
+
+    pub fn rotation_z(&self) -> f32 {
+        self.rotation_z
+    }
+
+    pub fn rotation(&self) -> na::Matrix4<f32> {
+        // The map is tilted away from the viewer, so the top of the screen is farther than the
+        // bottom and the objects above the ground lean to the top of the screen.
+        let x = na::Matrix4::from_axis_angle(
+            &na::Unit::new_normalize(na::Vector3::new(1.0, 0.0, 0.0)),
+            -self.rotation_x,
+        );
+        let z = na::Matrix4::from_axis_angle(
+            &na::Unit::new_normalize(na::Vector3::new(0.0, 0.0, 1.0)),
+            self.rotation_z,
+        );
+        x * z
+    }
+
+    pub fn inverse_rotation(&self) -> na::Matrix4<f32> {
+        let x = na::Matrix4::from_axis_angle(
+            &na::Unit::new_normalize(na::Vector3::new(1.0, 0.0, 0.0)),
+            self.rotation_x,
+        );
+        let z = na::Matrix4::from_axis_angle(
+            &na::Unit::new_normalize(na::Vector3::new(0.0, 0.0, 1.0)),
+            -self.rotation_z,
+        );
+        z * x
+    }
+
+    pub fn zoom(&mut self, delta: f32, center_px: [i32; 2]) {
+        let resolution = self.resolution();
+        let new_resolution = self
+            .constraints
+            .zoom_resolution(resolution, resolution / delta);
+        let delta = resolution / new_resolution;
+
+        // the point under the cursor must stay at the same place
+        let zoom_c = self.get_map_position(&center_px);
+        let map_c = self.center();
+
+        let dx = (map_c[0] - zoom_c[0]) / delta as f64;
+        let dy = (map_c[1] - zoom_c[1]) / delta as f64;
+
+        self.set_resolution(new_resolution);
+        self.set_center(zoom_c[0] + dx, zoom_c[1] + dy);
+    }
+
+    /// Transformation from the map coordinates into the clip space.
+    ///
+    /// Calculations with big coordinate values in single precision are not accurate, so for
+    /// rendering use `matrix_relative_to` instead.
+    pub fn matrix(&self) -> na::Matrix4<f32> {
+        self.matrix_relative_to([0.0, 0.0, 0.0])
+    }
+
+    /// Transformation from the coordinates relative to the `origin` into the clip space.
+    ///
+    /// The translation between the origin and the map center is calculated in double precision,
+    /// so the geometries close to the origin are displayed without rounding errors even if their
+    /// absolute coordinates are too big for `f32`.
+    pub fn matrix_relative_to(&self, origin: Point3) -> na::Matrix4<f32> {
+        let translation = na::Matrix4::new_translation(&na::Vector3::new(
+            (origin[0] - self.center[0]) as f32,
+            (origin[1] - self.center[1]) as f32,
+            origin[2] as f32,
+        ));
+        self.projection() * self.scale() * self.rotation() * translation
+    }
+
+    /// Transformation from the camera space in pixels into the clip space.
+    pub fn projection(&self) -> na::Matrix4<f32> {
+        camera::projection_matrix(
+            self.camera_mode,
+            self.width_px(),
+            self.height_px(),
+            self.rotation_x,
+        )
+    }
+
+    pub fn set_screen_size(&mut self, width: u32, height: u32) {
+        self.screen_scale = na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(
+            2.0 / width as f32,
+            2.0 / height as f32,
+            2.0 / width as f32,
+        ));
+    }
+
+    pub fn inverse_scale(&self) -> na::Matrix4<f32> {
+        na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(
+            self.resolution,
+            self.resolution,
+            self.resolution,
+        ))
+    }
+
+    pub fn center(&self) -> Point {
+        self.center
+    }
+
+    pub fn resolution(&self) -> f32 {
+        self.resolution
+    }
+
+    fn scale(&self) -> na::Matrix4<f32> {
+        na::Matrix4::new_scaling(1.0 / self.resolution)
+    }
+
+    pub fn inverse_translation(&self) -> na::Matrix4<f32> {
+        na::Matrix4::new_translation(&na::Vector3::new(
+            self.center[0] as f32,
+            self.center[1] as f32,
+            0.0,
+        ))
+    }
+
+    pub fn half_screen_translation(&self) -> na::Matrix4<f32> {
+        na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(1.0, -1.0, 1.0))
+            * na::Matrix4::new_translation(&na::Vector3::new(
+                -self.width_px() / 2.0,
+                -self.height_px() / 2.0,
+                0.0,
+            ))
+    }
+
+    /// Transformation from the screen pixel coordinates (with the depth in clip space as `z`)
+    /// into the map coordinates.
+    pub fn inverse_screen_transformation(&self) -> na::Matrix4<f32> {
+        self.inverse_translation() * self.inverse_relative_transformation()
+    }
+
+    /// Same as `inverse_screen_transformation`, but the result is relative to the map center.
+    fn inverse_relative_transformation(&self) -> na::Matrix4<f32> {
+        let screen_to_clip = na::Matrix4::new_nonuniform_scaling(&na::Vector3::new(
+            2.0 / self.width_px(),
+            2.0 / self.height_px(),
+            1.0,
+        )) * self.half_screen_translation();
+
+        self.matrix_relative_to([self.center[0], self.center[1], 0.0])
+            .try_inverse()
+            .unwrap_or_else(na::Matrix4::identity)
+            * screen_to_clip
+    }
+
+    /// Returns the point on the ground displayed at the given pixel. If the pixel is above the
+    /// horizon, the farthest visible point in the direction of the pixel is returned.
+    pub fn get_map_position(&self, px_position: &[i32; 2]) -> Point {
+        let px_position = [px_position[0] as f32, px_position[1] as f32];
+        let (point, _) = self.ground_point(&self.inverse_relative_transformation(), px_position);
+        point
+    }
+
+    /// Returns geographic coordinates `[lon, lat]` of the point displayed at the given pixel.
+    /// Use `crs::format_lon_lat` to show them to the user.
+    pub fn get_lon_lat(&self, px_position: &[i32; 2]) -> Point {
+        self.to_lon_lat(self.get_map_position(px_position))
+    }
+
+    /// Converts the point from the map CRS into geographic coordinates `[lon, lat]`.
+    pub fn to_lon_lat(&self, point: Point) -> Point {
+        let [lon, lat, _] = self.crs.projection().unproject([point[0], point[1], 0.0]);
+        // Points on the copies of the world have longitude outside of [-180; 180]
+        [(lon + 180.0).rem_euclid(360.0) - 180.0, lat]
+    }
+
+    /// Converts geographic coordinates `[lon, lat]` into the map CRS.
+    pub fn from_lon_lat(&self, lon_lat: Point) -> Point {
+        let [x, y, _] = self.crs.projection().project([lon_lat[0], lon_lat[1], 0.0]);
+        [x, y]
+    }
+
+    /// Moves the map center to the given geographic coordinates. Together with
+    /// `crs::parse_lon_lat` this can be used to go to a coordinate typed by the user.
+    pub fn set_center_lon_lat(&mut self, lon_lat: Point) {
+        let [x, y] = self.from_lon_lat(lon_lat);
+        self.set_center(x, y);
+    }
+
+    /// Returns the point on the ground plane (`z == 0`) that is displayed at the given pixel,
+    /// or `None` if the pixel is above the horizon.
+    ///
+    /// The pixel is unprojected into a ray going through the screen, and the point is found as
+    /// the intersection of the ray with the ground, so the result is correct for tilted maps.
+    pub fn screen_to_map(&self, px_position: [f32; 2]) -> Option<Point> {
+        match self.ground_point(&self.inverse_relative_transformation(), px_position) {
+            (point, true) => Some(point),
+            (_, false) => None,
+        }
+    }
+
+    /// Returns the intersection of the ground and the ray going through the pixel, and whether
+    /// such intersection exists. If there is no intersection, the point at the far clip plane
+    /// projected onto the ground is returned.
+    ///
+    /// `inverse` is the transformation returned by `inverse_relative_transformation`.
+    fn ground_point(&self, inverse: &na::Matrix4<f32>, px_position: [f32; 2]) -> (Point, bool) {
+        let (point, is_on_ground) = self.relative_ground_point(inverse, px_position);
+        (
+            [
+                self.center[0] + point[0] as f64,
+                self.center[1] + point[1] as f64,
+            ],
+            is_on_ground,
+        )
+    }
+
+    fn relative_ground_point(
+        &self,
+        inverse: &na::Matrix4<f32>,
+        px_position: [f32; 2],
+    ) -> ([f32; 2], bool) {
+        let near = inverse * na::Vector4::new(px_position[0], px_position[1], -1.0, 1.0);
+        let far = inverse * na::Vector4::new(px_position[0], px_position[1], 1.0, 1.0);
+        let near = near.xyz() / near[3];
+        let far = far.xyz() / far[3];
+        let direction = far - near;
+        if direction[2].abs() < f32::EPSILON {
+            return ([near[0], near[1]], true);
+        }
+
+        let t = -near[2] / direction[2];
+        let is_perspective = matches!(self.camera_mode, CameraMode::Perspective { .. });
+        if is_perspective && t < 0.0 {
+            return ([far[0], far[1]], false);
+        }
+
+        (
+            [near[0] + direction[0] * t, near[1] + direction[1] * t],
+            true,
+        )
+    }
+
+    /// Returns the screen position in pixels where the given map point is displayed, or `None`
+    /// if the point is behind the camera.
+    pub fn map_to_screen(&self, point: Point3) -> Option<[f32; 2]> {
+        let matrix = self.matrix_relative_to([self.center[0], self.center[1], 0.0]);
+        let projected = matrix
+            * na::Vector4::new(
+                (point[0] - self.center[0]) as f32,
+                (point[1] - self.center[1]) as f32,
+                point[2] as f32,
+                1.0,
+            );
+        if projected[3] <= 0.0 {
+            return None;
+        }
+
+        let x = projected[0] / projected[3];
+        let y = projected[1] / projected[3];
+        Some([
+            (x + 1.0) / 2.0 * self.width_px(),
+            (1.0 - y) / 2.0 * self.height_px(),
+        ])
+    }
+
+    /// Returns the area of the map that is visible on the screen as the map points displayed at
+    /// the screen corners: top left, top right, bottom right and bottom left. If a corner is
+    /// above the horizon, the farthest visible point in its direction is used.
+    pub fn visible_footprint(&self) -> [Point; 4] {
+        let width = self.width_px();
+        let height = self.height_px();
+        let inverse = self.inverse_relative_transformation();
+        [
+            self.ground_point(&inverse, [0.0, 0.0]).0,
+            self.ground_point(&inverse, [width, 0.0]).0,
+            self.ground_point(&inverse, [width, height]).0,
+            self.ground_point(&inverse, [0.0, height]).0,
+        ]
+    }
+
+    /// Returns the bounding box of the area of the map that is visible on the screen.
+    pub fn visible_extent(&self) -> BoundingBox {
+        let footprint = self.visible_footprint();
+        footprint.iter().skip(1).fold(
+            [
+                footprint[0][0],
+                footprint[0][1],
+                footprint[0][0],
+                footprint[0][1],
+            ],
+            |bbox, p| {
+                [
+                    bbox[0].min(p[0]),
+                    bbox[1].min(p[1]),
+                    bbox[2].max(p[0]),
+                    bbox[3].max(p[1]),
+                ]
+            },
+        )
+    }
+
+    /// Sets the center and resolution of the position so that the whole `bbox` is visible on
+    /// the screen with at least `padding_px` pixels between the box and the screen edges.
+    /// Current rotation and tilt of the map are preserved.
+    pub fn fit_bounds(&mut self, bbox: BoundingBox, padding_px: f32) {
+        let center = [(bbox[0] + bbox[2]) / 2.0, (bbox[1] + bbox[3]) / 2.0];
+        self.set_center(center[0], center[1]);
+
+        let (sin, cos) = (self.rotation_z as f64).sin_cos();
+        let mut half_width: f64 = 0.0;
+        let mut half_height: f64 = 0.0;
+        for (x, y) in [
+            (bbox[0], bbox[1]),
+            (bbox[0], bbox[3]),
+            (bbox[2], bbox[1]),
+            (bbox[2], bbox[3]),
+        ] {
+            let dx = x - center[0];
+            let dy = y - center[1];
+            half_width = half_width.max((dx * cos - dy * sin).abs());
+            half_height = half_height.max((dx * sin + dy * cos).abs());
+        }
+
+        // tilted map is shortened along the screen vertical axis
+        half_height *= (self.rotation_x as f64).cos();
+
+        let available = |size: f32| {
+            if size > 2.0 * padding_px {
+                size - 2.0 * padding_px
+            } else {
+                size
+            }
+        };
+        let resolution = ((2.0 * half_width) as f32 / available(self.width_px()))
+            .max((2.0 * half_height) as f32 / available(self.height_px()));
+
+        if resolution > 0.0 && resolution.is_finite() {
+            self.set_resolution(resolution);
+        }
+
+        if let CameraMode::Perspective { .. } = self.camera_mode {
+            // Perspective makes the near part of the box bigger than the far one, so the
+            // resolution is adjusted by the actual position of the box corners on the screen.
+            const ITERATIONS: usize = 4;
+            for _ in 0..ITERATIONS {
+                self.fit_projected_bounds(bbox, padding_px);
+            }
+        }
+    }
+
+    fn fit_projected_bounds(&mut self, bbox: BoundingBox, padding_px: f32) {
+        let half_width = self.width_px() / 2.0;
+        let half_height = self.height_px() / 2.0;
+        let mut ratio: f32 = 0.0;
+        for (x, y) in [
+            (bbox[0], bbox[1]),
+            (bbox[0], bbox[3]),
+            (bbox[2], bbox[1]),
+            (bbox[2], bbox[3]),
+        ] {
+            let screen = match self.map_to_screen([x, y, 0.0]) {
+                Some(screen) => screen,
+                None => return,
+            };
+
+            ratio = ratio
+                .max((screen[0] - half_width).abs() / (half_width - padding_px).max(1.0))
+                .max((screen[1] - half_height).abs() / (half_height - padding_px).max(1.0));
+        }
+
+        if ratio > 0.0 && ratio.is_finite() {
+            self.set_resolution(self.resolution() * ratio);
+        }
+    }
+}
+
+impl Default for MapPosition {
+    fn default() -> Self {
+        use num_traits::identities::One;
+
+        Self {
+            screen_scale: na::Matrix4::one(),
+            resolution: 1.0,
+            center: [0.0, 0.0],
+            rotation_x: 0.0,
+            rotation_z: 0.0,
+            constraints: ViewConstraints::default(),
+            camera_mode: CameraMode::default(),
+            crs: Crs::default(),
+            wrap_world: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_set_center() {
+        let mut position = MapPosition::default();
+        position.set_center(-10.0, 10.0);
+        assert_eq!(-10.0, position.center()[0]);
+        assert_eq!(10.0, position.center()[1]);
+    }
+
+    #[test]
+    fn position_respects_constraints() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(100, 100);
+        position.set_constraints(
+            ViewConstraints::default()
+                .with_resolution_range(1.0, 10.0)
+                .with_max_extent([0.0, 0.0, 1000.0, 1000.0], ExtentMode::Viewport)
+                .with_rotation(false),
+        );
+
+        position.set_resolution(100.0);
+        assert_eq!(10.0, position.resolution());
+        assert_near([500.0, 500.0], position.center(), 1e-4);
+
+        position.zoom(1000.0, [50, 50]);
+        assert_eq!(1.0, position.resolution());
+
+        position.translate_px(10_000, 0);
+        assert_near([50.0, 500.0], position.center(), 1e-4);
+
+        position.rotate(0.0, 1.0);
+        assert_eq!(0.0, position.rotation_z());
+    }
+
+    #[test]
+    fn position_visible_extent() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(200, 100);
+        position.set_center(1000.0, 0.0);
+        position.set_resolution(2.0);
+
+        let extent = position.visible_extent();
+        assert_near([800.0, -100.0], [extent[0], extent[1]], 1e-4);
+        assert_near([1200.0, 100.0], [extent[2], extent[3]], 1e-4);
+        assert_near([800.0, 100.0], position.visible_footprint()[0], 1e-4);
+
+        position.set_rotation(0.0, std::f32::consts::FRAC_PI_2);
+        let extent = position.visible_extent();
+        assert!((extent[0] - 900.0).abs() < 1e-3);
+        assert!((extent[3] - 200.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn position_visible_extent_tilted() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(100, 100);
+        position.set_rotation(0.5, 0.0);
+
+        let extent = position.visible_extent();
+        assert!((extent[0] + 50.0).abs() < 1e-3);
+        assert!((extent[3] - 50.0 / 0.5f64.cos()).abs() < 1e-3);
+    }
+
+    fn assert_near<T: Into<f64> + Copy + std::fmt::Debug>(
+        expected: [T; 2],
+        actual: [T; 2],
+        tolerance: f64,
+    ) {
+        let (expected, actual) = (
+            [expected[0].into(), expected[1].into()],
+            [actual[0].into(), actual[1].into()],
+        );
+        let tolerance = tolerance * expected[0].abs().max(expected[1].abs()).max(100.0);
+        assert!(
+            (expected[0] - actual[0]).abs() < tolerance
+                && (expected[1] - actual[1]).abs() < tolerance,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    fn transformed_position(rotation_x: f32, rotation_z: f32, mode: CameraMode) -> MapPosition {
+        let mut position = MapPosition::default();
+        position.set_screen_size(800, 600);
+        position.set_camera_mode(mode);
+        position.set_center(1000.0, -500.0);
+        position.set_resolution(2.5);
+        position.set_rotation(rotation_x, rotation_z);
+        position
+    }
+
+    const ROTATIONS: [(f32, f32); 6] = [
+        (0.0, 0.0),
+        (0.0, 1.0),
+        (0.3, 0.0),
+        (0.6, -2.0),
+        (1.0, 0.7),
+        (1.1, 3.0),
+    ];
+
+    fn positions() -> Vec<MapPosition> {
+        let mut positions = vec![];
+        for mode in [CameraMode::Orthographic, CameraMode::perspective()] {
+            for (rotation_x, rotation_z) in ROTATIONS {
+                positions.push(transformed_position(rotation_x, rotation_z, mode));
+            }
+        }
+
+        positions
+    }
+
+    #[test]
+    fn screen_center_is_map_center() {
+        for position in positions() {
+            assert_near(
+                [1000.0, -500.0],
+                position.screen_to_map([400.0, 300.0]).unwrap(),
+                1e-4,
+            );
+            assert_near(
+                [400.0, 300.0],
+                position.map_to_screen([1000.0, -500.0, 0.0]).unwrap(),
+                1e-4,
+            );
+        }
+    }
+
+    #[test]
+    fn unprojection_round_trip() {
+        for position in positions() {
+            for px in [[0.0, 0.0], [800.0, 0.0], [123.0, 456.0], [800.0, 600.0]] {
+                let map_point = position.screen_to_map(px).unwrap();
+                assert_near(
+                    px,
+                    position
+                        .map_to_screen([map_point[0], map_point[1], 0.0])
+                        .unwrap(),
+                    1e-4,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unprojection_of_tilted_map() {
+        let position = transformed_position(1.0, 0.0, CameraMode::Orthographic);
+        let top = position.screen_to_map([400.0, 0.0]).unwrap();
+        assert_near([1000.0, -500.0 + 300.0 * 2.5 / 1f64.cos()], top, 1e-4);
+
+        let right = position.screen_to_map([800.0, 300.0]).unwrap();
+        assert_near([1000.0 + 400.0 * 2.5, -500.0], right, 1e-4);
+    }
+
+    #[test]
+    fn perspective_makes_far_objects_smaller() {
+        let position = transformed_position(1.0, 0.0, CameraMode::perspective());
+        let top = position.screen_to_map([400.0, 0.0]).unwrap();
+        let bottom = position.screen_to_map([400.0, 600.0]).unwrap();
+        assert!(top[1] - (-500.0) > (-500.0) - bottom[1]);
+
+        let ground = position.map_to_screen([1000.0, -500.0, 0.0]).unwrap();
+        let elevated = position.map_to_screen([1000.0, -500.0, 100.0]).unwrap();
+        assert!(elevated[1] < ground[1]);
+    }
+
+    #[test]
+    fn perspective_horizon() {
+        let mut position =
+            transformed_position(1.1, 0.0, CameraMode::Perspective { field_of_view: 1.2 });
+        position.set_screen_size(800, 600);
+        assert_eq!(None, position.screen_to_map([400.0, 0.0]));
+        assert!(position.screen_to_map([400.0, 600.0]).is_some());
+
+        let extent = position.visible_extent();
+        assert!(extent[3].is_finite() && extent[3] > -500.0);
+    }
+
+    #[test]
+    fn zoom_keeps_cursor_point() {
+        for mut position in positions() {
+            let before = position.get_map_position(&[100, 50]);
+            position.zoom(2.0, [100, 50]);
+            assert_near(before, position.get_map_position(&[100, 50]), 1e-4);
+        }
+    }
+
+    #[test]
+    fn translate_moves_point_with_cursor() {
+        for mut position in positions() {
+            let before = position.get_map_position(&[100, 50]);
+            position.drag_px([100.0, 50.0], [130.0, 70.0]);
+            assert_near(before, position.get_map_position(&[130, 70]), 1e-3);
+
+            let center = position.center();
+            position.translate_px(30, -20);
+            assert_near(center, position.get_map_position(&[430, 320]), 1e-3);
+        }
+    }
+
+    #[test]
+    fn rotation_follows_cursor() {
+        for mut position in positions() {
+            let center = position.center();
+            let angle = |point: Point| (point[1] - center[1]).atan2(point[0] - center[0]);
+
+            let before = position.get_map_position(&[600, 150]);
+            position.rotate_px([600.0, 150.0], [250.0, 200.0]);
+            let after = position.get_map_position(&[250, 200]);
+            let difference = (angle(after) - angle(before)).rem_euclid(2.0 * std::f64::consts::PI);
+            assert!(
+                !(1e-3..=2.0 * std::f64::consts::PI - 1e-3).contains(&difference),
+                "{:?}: {}",
+                position.view_state(),
+                difference
+            );
+        }
+    }
+
+    #[test]
+    fn perspective_fit_bounds() {
+        let mut position = transformed_position(1.0, 0.5, CameraMode::perspective());
+        position.fit_bounds([0.0, 0.0, 1000.0, 1000.0], 10.0);
+        for corner in [[0.0, 0.0, 0.0], [0.0, 1000.0, 0.0], [1000.0, 1000.0, 0.0]] {
+            let screen = position.map_to_screen(corner).unwrap();
+            assert!(screen[0] >= 9.0 && screen[0] <= 791.0);
+            assert!(screen[1] >= 9.0 && screen[1] <= 591.0);
+        }
+    }
+
+    #[test]
+    fn position_fit_bounds() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(200, 100);
+        position.fit_bounds([0.0, 0.0, 1000.0, 1000.0], 0.0);
+        assert_eq!([500.0, 500.0], position.center());
+        assert_eq!(10.0, position.resolution());
+
+        position.fit_bounds([0.0, 0.0, 1000.0, 1000.0], 10.0);
+        assert_eq!(1000.0 / 80.0, position.resolution());
+    }
+
+    #[test]
+    fn position_fit_bounds_rotated() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(100, 100);
+        position.set_rotation(0.0, std::f32::consts::FRAC_PI_4);
+        position.fit_bounds([0.0, 0.0, 100.0, 100.0], 0.0);
+        assert!((position.resolution() - 2f32.sqrt()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn queued_animations_run_one_after_another() {
+        let mut map = Map::new();
+        let mut first = MapPosition::default();
+        first.set_center(10.0, 0.0);
+        let mut second = MapPosition::default();
+        second.set_center(10.0, 10.0);
+
+        map.queue_animation(first, 100, Easing::Linear);
+        map.queue_animation(second, 100, Easing::Linear);
+
+        let start = instant::Instant::now();
+        map.animation_frame_at(start);
+        map.animation_frame_at(start + instant::Duration::from_millis(100));
+        assert_eq!([10.0, 0.0], map.position().center());
+        assert!(map.is_animating());
+
+        map.animation_frame_at(start + instant::Duration::from_millis(150));
+        assert_eq!([10.0, 5.0], map.position().center());
+
+        map.cancel_animation();
+        assert!(!map.is_animating());
+        assert_eq!([10.0, 5.0], map.position().center());
+    }
+
+    #[test]
+    fn history_records_animations() {
+        let mut map = Map::new();
+        let mut target = MapPosition::default();
+        target.set_center(10.0, 0.0);
+        map.animate_to(target, 100);
+
+        let start = instant::Instant::now();
+        map.animation_frame_at(start);
+        map.animation_frame_at(start + instant::Duration::from_millis(100));
+        assert_eq!(2, map.history().len());
+
+        assert!(map.back());
+        assert_eq!([0.0, 0.0], map.position().center());
+        assert!(!map.back());
+        assert!(map.forward());
+        assert_eq!([10.0, 0.0], map.position().center());
+        assert!(!map.forward());
+    }
+
+    #[test]
+    fn geographic_coordinates() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(800, 600);
+        position.set_resolution(10.0);
+        position.set_center_lon_lat([37.6, 55.75]);
+
+        let [lon, lat] = position.get_lon_lat(&[400, 300]);
+        assert!((lon - 37.6).abs() < 1e-6 && (lat - 55.75).abs() < 1e-6);
+
+        let [lon, lat] = position.get_lon_lat(&[500, 300]);
+        assert!(lon > 37.6 && (lat - 55.75).abs() < 1e-6);
+    }
+
+    #[test]
+    fn world_copies_are_visible_across_antimeridian() {
+        let mut position = MapPosition::default();
+        assert!(!position.wrap_world());
+        position.set_wrap_world(true);
+        position.set_screen_size(800, 600);
+        position.set_crs(Crs::wgs84());
+        position.set_resolution(0.1);
+        position.set_center(180.0, 0.0);
+
+        // Visible area is [140; 220] along x
+        let bbox = [-170.0, -10.0, -160.0, 10.0];
+        assert_eq!(vec![360.0], position.world_offsets(&bbox, 0.0));
+        let bbox = [-179.0, -10.0, 179.0, 10.0];
+        assert_eq!(vec![0.0, 360.0], position.world_offsets(&bbox, 0.0));
+        let bbox = [-179.0, 50.0, 179.0, 60.0];
+        assert!(position.world_offsets(&bbox, 0.0).is_empty());
+
+        let [lon, _] = position.get_lon_lat(&[500, 300]);
+        assert!((lon + 170.0).abs() < 1e-3);
+
+        position.set_wrap_world(false);
+        let bbox = [-170.0, -10.0, -160.0, 10.0];
+        assert!(position.world_offsets(&bbox, 0.0).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use crate::map::MapPosition;
+use crate::Point;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Stable representation of the map view, independent of the screen size.
+///
+/// The string form is `x,y,resolution` followed by `,rotation_x,rotation_z` if the map is
+/// rotated or tilted. Angles are in radians. All the numbers are written with the shortest
+/// representation that is parsed back to the same value, so the conversion is lossless.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ViewState {
+    pub center: Point,
+    pub resolution: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rotation_x: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rotation_z: f32,
+}
+
+impl ViewState {
+    pub fn new(center: Point, resolution: f32) -> Self {
+        Self {
+            center,
+            resolution,
+            rotation_x: 0.0,
+            rotation_z: 0.0,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation_x: f32, rotation_z: f32) -> Self {
+        self.rotation_x = rotation_x;
+        self.rotation_z = rotation_z;
+        self
+    }
+}
+
+impl From<&MapPosition> for ViewState {
+    fn from(position: &MapPosition) -> Self {
+        position.view_state()
+    }
+}
+
+impl Display for ViewState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{},{},{}",
+            self.center[0], self.center[1], self.resolution
+        )?;
+        if self.rotation_x != 0.0 || self.rotation_z != 0.0 {
+            write!(f, ",{},{}", self.rotation_x, self.rotation_z)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseViewStateError {
+    /// The string does not have 3 or 5 comma separated values.
+    InvalidFormat,
+    InvalidNumber(String),
+    /// The value is `NaN` or infinite, or does not fit into the field type.
+    NonFiniteNumber(String),
+    /// Resolution is not a positive number.
+    InvalidResolution,
+}
+
+impl Display for ParseViewStateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseViewStateError::InvalidFormat => write!(
+                f,
+                "expected `x,y,resolution` or `x,y,resolution,rotation_x,rotation_z`"
+            ),
+            ParseViewStateError::InvalidNumber(value) => write!(f, "invalid number `{}`", value),
+            ParseViewStateError::NonFiniteNumber(value) => {
+                write!(f, "number `{}` is not finite", value)
+            }
+            ParseViewStateError::InvalidResolution => write!(f, "resolution must be positive"),
+        }
+    }
+}
+
+impl std::error::Error for ParseViewStateError {}
+
+impl FromStr for ViewState {
+    type Err = ParseViewStateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().trim_start_matches('#').split(',').collect();
+        if parts.len() != 3 && parts.len() != 5 {
+            return Err(ParseViewStateError::InvalidFormat);
+        }
+
+        // The string can come from the page URL, so `NaN` and infinity, which `f64` parses,
+        // are rejected to not break the map position
+        fn parse(value: &str) -> Result<f64, ParseViewStateError> {
+            let number: f64 = value
+                .trim()
+                .parse()
+                .map_err(|_| ParseViewStateError::InvalidNumber(value.to_string()))?;
+            if number.is_finite() {
+                Ok(number)
+            } else {
+                Err(ParseViewStateError::NonFiniteNumber(value.to_string()))
+            }
+        }
+
+        fn parse_f32(value: &str) -> Result<f32, ParseViewStateError> {
+            let number = parse(value)? as f32;
+            if number.is_finite() {
+                Ok(number)
+            } else {
+                Err(ParseViewStateError::NonFiniteNumber(value.to_string()))
+            }
+        }
+
+        let x = parse(parts[0])?;
+        let y = parse(parts[1])?;
+        let resolution = parse_f32(parts[2])?;
+        if resolution <= 0.0 {
+            return Err(ParseViewStateError::InvalidResolution);
+        }
+
+        let mut state = ViewState::new([x, y], resolution);
+        if parts.len() == 5 {
+            state = state.with_rotation(parse_f32(parts[3])?, parse_f32(parts[4])?);
+        }
+
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_round_trip() {
+        let state = ViewState::new([4_187_123.456_789, -0.1], 0.3).with_rotation(0.5, -2.0);
+        assert_eq!(state, state.to_string().parse().unwrap());
+
+        let state = ViewState::new([10.0, 20.0], 2.0);
+        assert_eq!("10,20,2", state.to_string());
+        assert_eq!(state, "#10,20,2".parse().unwrap());
+    }
+
+    #[test]
+    fn invalid_strings() {
+        assert_eq!(
+            Err(ParseViewStateError::InvalidFormat),
+            "1,2".parse::<ViewState>()
+        );
+        assert_eq!(
+            Err(ParseViewStateError::InvalidNumber("a".into())),
+            "1,a,3".parse::<ViewState>()
+        );
+        assert_eq!(
+            Err(ParseViewStateError::InvalidResolution),
+            "1,2,0".parse::<ViewState>()
+        );
+    }
+
+    #[test]
+    fn non_finite_numbers() {
+        assert_eq!(
+            Err(ParseViewStateError::NonFiniteNumber("NaN".into())),
+            "NaN,0,1".parse::<ViewState>()
+        );
+        assert_eq!(
+            Err(ParseViewStateError::NonFiniteNumber("inf".into())),
+            "0,0,1,inf,0".parse::<ViewState>()
+        );
+        assert_eq!(
+            Err(ParseViewStateError::NonFiniteNumber("-inf".into())),
+            "0,-inf,1".parse::<ViewState>()
+        );
+        assert_eq!(
+            Err(ParseViewStateError::NonFiniteNumber("1e40".into())),
+            "0,0,1,0,1e40".parse::<ViewState>()
+        );
+    }
+
+    #[test]
+    fn position_round_trip() {
+        let mut position = MapPosition::default();
+        position.set_screen_size(800, 600);
+        position.set_resolution(12.5);
+        position.set_rotation(0.3, 1.0);
+        position.set_center(1_000_000.25, -3.5);
+
+        let state = position.view_state();
+        let mut restored = MapPosition::default();
+        restored.set_screen_size(400, 300);
+        restored.set_view_state(&state);
+        assert_eq!(state, restored.view_state());
+    }
+}
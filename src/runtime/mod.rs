@@ -48,6 +48,8 @@ fn event_loop_cycle(
             gl.clear_color(0.1, 0.2, 0.3, 1.0);
             gl.clear(glow::COLOR_BUFFER_BIT);
 
+            map.animation_frame();
+
             let mut target = RenderTarget::new(gl, (width, height));
             map.draw(&mut target);
 
@@ -39,7 +39,10 @@ impl NativeRuntime {
             gl
         };
 
-        let map = Map::new();
+        let mut map = Map::new();
+        let size = window.window().inner_size();
+        map.position_mut().set_screen_size(size.width, size.height);
+
         let control = DefaultMapControl::new();
 
         Self {
@@ -1,5 +1,5 @@
 use crate::control::DefaultMapControl;
-use crate::map::Map;
+use crate::map::{Map, ViewState};
 use crate::render_target::RenderTarget;
 use glow::{Context, HasContext};
 use std::cell::RefCell;
@@ -16,6 +16,7 @@ pub struct WasmRuntime {
     context: Context,
     event_loop: winit::event_loop::EventLoop<()>,
     window: winit::window::Window,
+    sync_location_hash: bool,
 }
 
 impl WasmRuntime {
@@ -54,7 +55,9 @@ impl WasmRuntime {
             gl.blend_func(glow::SRC_ALPHA, glow::ONE_MINUS_SRC_ALPHA);
         }
 
-        let map = Map::new();
+        let mut map = Map::new();
+        map.position_mut().set_screen_size(width, height);
+
         let control = DefaultMapControl::new();
         Self {
             map,
@@ -62,9 +65,16 @@ impl WasmRuntime {
             context: gl,
             event_loop,
             control,
+            sync_location_hash: false,
         }
     }
 
+    /// If enabled, the map view is restored from `location.hash` on start and the hash is
+    /// updated when the view changes. Changes of the hash by the user move the map.
+    pub fn sync_location_hash(&mut self, enabled: bool) {
+        self.sync_location_hash = enabled;
+    }
+
     pub fn map_mut(&mut self) -> &mut Map {
         &mut self.map
     }
@@ -76,15 +86,22 @@ impl WasmRuntime {
             context,
             event_loop,
             window,
+            sync_location_hash,
         } = self;
         let gl = Rc::new(context);
         let map = Rc::new(RefCell::new(map));
         control.attach(map.clone());
 
+        let mut hash_sync = if sync_location_hash {
+            Some(LocationHashSync::default())
+        } else {
+            None
+        };
+
         event_loop.run(move |event, _, control_flow| {
             let size = window.inner_size();
 
-            super::event_loop_cycle(
+            let redraw_requested = super::event_loop_cycle(
                 event,
                 control_flow,
                 &mut *map.borrow_mut(),
@@ -92,6 +109,47 @@ impl WasmRuntime {
                 size.width,
                 size.height,
             );
+
+            if redraw_requested {
+                if let Some(hash_sync) = &mut hash_sync {
+                    hash_sync.update(&mut *map.borrow_mut());
+                }
+            }
         });
     }
 }
+
+/// Keeps the map view and `location.hash` in sync.
+#[derive(Default)]
+struct LocationHashSync {
+    /// The last hash value set by this object or applied to the map.
+    hash: Option<String>,
+}
+
+impl LocationHashSync {
+    fn update(&mut self, map: &mut Map) {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+
+        let hash = window.location().hash().unwrap_or_default();
+        if self.hash.as_ref() != Some(&hash) {
+            // The hash was changed outside of the map, or this is the first frame
+            if let Ok(state) = hash.parse::<ViewState>() {
+                map.cancel_animation();
+                map.position_mut().set_view_state(&state);
+            }
+        }
+
+        let new_hash = format!("#{}", map.position().view_state());
+        if new_hash != hash {
+            // Replacing the state does not create a new history entry on every frame
+            let _ = window.history().and_then(|history| {
+                history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&new_hash))
+            });
+        }
+
+        self.hash = Some(new_hash);
+    }
+}
@@ -1,6 +1,8 @@
+use crate::geometry::Geometry;
 use crate::gl::{AttributeValueType, Vertex, VertexAttribute};
-use crate::symbol::Symbol;
-use crate::{Color, Point, Point3};
+use crate::symbol::line::LineVertex;
+use crate::symbol::{merge_parts, relative_position, Symbol};
+use crate::{Color, Point3};
 use glow::Program;
 
 pub struct CircleSymbol {
@@ -19,17 +21,25 @@ layout (location = 4) in uint id;
 uniform mat4 transformation;
 uniform vec2 screen_size;
 uniform uint mode;
+uniform float opacity;
 
 out vec4 frag_color;
 
 void main() {
     vec2 dir = direction * size / screen_size;
-    gl_Position = vec4((vec4(position.xyz, 1.0) * transformation + vec4(dir, 0.0, 0.0)).xy, 0.0, 1.0);
+    vec4 projected = vec4(position.xyz, 1.0) * transformation;
+    gl_Position = projected + vec4(dir * projected.w, 0.0, 0.0);
     if (mode == 0u) {
-        frag_color = color;
+        frag_color = vec4(color.rgb, color.a * opacity);
     }
     if (mode == 1u) {
-        frag_color = vec4((float(id) + 1.0) / 255.0, 0.0, 0.0, 1.0);
+        uint value = id + 1u;
+        frag_color = vec4(
+            float(value & 255u),
+            float((value >> 8) & 255u),
+            float((value >> 16) & 255u),
+            255.0
+        ) / 255.0;
     }
 }
 "#;
@@ -64,13 +74,19 @@ impl Symbol<Point3> for CircleSymbol {
         self.program.as_ref()
     }
 
-    fn convert(&self, point: &Point3, id: u32) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+    fn convert(
+        &self,
+        point: &Point3,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
         let mut result = vec![];
+        let position = relative_position(point, &origin);
         const SEGMENTS: usize = 16;
 
         for i in 0..SEGMENTS {
             result.push(CirclePointVertex {
-                position: *point,
+                position,
                 direction: [0.0, 0.0],
                 size: self.size,
                 color: self.color,
@@ -85,7 +101,7 @@ impl Symbol<Point3> for CircleSymbol {
             let dy = angle.sin();
 
             result.push(CirclePointVertex {
-                position: *point,
+                position,
                 direction: [dx, dy],
                 size: self.size,
                 color: self.color,
@@ -99,7 +115,7 @@ impl Symbol<Point3> for CircleSymbol {
             let dy = angle.sin();
 
             result.push(CirclePointVertex {
-                position: *point,
+                position,
                 direction: [dx, dy],
                 size: self.size,
                 color: self.color,
@@ -111,16 +127,76 @@ impl Symbol<Point3> for CircleSymbol {
     }
 }
 
+impl Symbol<Geometry> for CircleSymbol {
+    type Vertex = CirclePointVertex;
+
+    fn vertex_shader(&self) -> &str {
+        VERTEX_SHADER
+    }
+
+    fn fragment_shader(&self) -> &str {
+        FRAGMENT_SHADER
+    }
+
+    fn set_program(&mut self, program: Program) {
+        self.program = Some(program);
+    }
+
+    fn program(&self) -> Option<&Program> {
+        self.program.as_ref()
+    }
+
+    /// Converts the points of the geometry, other parts are ignored.
+    fn convert(
+        &self,
+        geometry: &Geometry,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+        merge_parts(
+            geometry
+                .points()
+                .into_iter()
+                .map(|part| Symbol::<Point3>::convert(self, part, id, origin)),
+        )
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Copy, Clone)]
 pub struct CirclePointVertex {
-    position: Point3,
-    direction: Point,
+    position: [f32; 3],
+    direction: [f32; 2],
     color: Color,
     size: f32,
     id: u32,
 }
 
+impl CirclePointVertex {
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Sets the size of the point in pixels.
+    pub fn set_size(&mut self, size: f32) {
+        self.size = size;
+    }
+}
+
+/// Vertex without the screen space offset, used to draw lines and polygons with the same
+/// program as the points.
+impl From<LineVertex> for CirclePointVertex {
+    fn from(vertex: LineVertex) -> Self {
+        Self {
+            position: vertex.position,
+            direction: [0.0, 0.0],
+            color: vertex.color,
+            size: 0.0,
+            id: vertex.id,
+        }
+    }
+}
+
 impl Vertex for CirclePointVertex {
     fn attributes() -> Vec<VertexAttribute> {
         vec![
@@ -0,0 +1,108 @@
+use crate::geometry::Geometry;
+use crate::symbol::{
+    merge_parts, CirclePointVertex, CircleSymbol, LineSymbol, PolygonSymbol, Symbol,
+};
+use crate::{Point3, Polygon, Polyline};
+use glow::Program;
+
+/// Symbol for the layers with mixed geometry types. Points are drawn with the `point` symbol,
+/// lines with the `line` symbol and polygons with the `polygon` symbol. All of them are drawn
+/// with a single program, so the programs of the nested symbols are not used.
+pub struct CompositeSymbol {
+    pub point: CircleSymbol,
+    pub line: LineSymbol,
+    pub polygon: PolygonSymbol,
+    pub program: Option<Program>,
+}
+
+impl Symbol<Geometry> for CompositeSymbol {
+    type Vertex = CirclePointVertex;
+
+    fn vertex_shader(&self) -> &str {
+        // Point vertex shader draws vertices without the offset as is
+        Symbol::<Point3>::vertex_shader(&self.point)
+    }
+
+    fn fragment_shader(&self) -> &str {
+        Symbol::<Point3>::fragment_shader(&self.point)
+    }
+
+    fn set_program(&mut self, program: Program) {
+        self.program = Some(program);
+    }
+
+    fn program(&self) -> Option<&Program> {
+        self.program.as_ref()
+    }
+
+    /// Polygons are drawn first, then lines, and points on top of them.
+    fn convert(
+        &self,
+        geometry: &Geometry,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+        let polygons = geometry.polygons().into_iter().map(|polygon| {
+            let (vertices, indices) =
+                Symbol::<Polygon>::convert(&self.polygon, polygon, id, origin);
+            (vertices.into_iter().map(Into::into).collect(), indices)
+        });
+        let lines = geometry.line_strings().into_iter().map(|line| {
+            let (vertices, indices) = Symbol::<Polyline>::convert(&self.line, line, id, origin);
+            (vertices.into_iter().map(Into::into).collect(), indices)
+        });
+        let points = geometry
+            .points()
+            .into_iter()
+            .map(|point| Symbol::<Point3>::convert(&self.point, point, id, origin));
+
+        merge_parts(polygons.chain(lines).chain(points))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_geometry() {
+        let symbol = CompositeSymbol {
+            point: CircleSymbol {
+                color: [1.0, 0.0, 0.0, 1.0],
+                size: 5.0,
+                program: None,
+            },
+            line: LineSymbol {
+                width: 2.0,
+                color: [0.0, 1.0, 0.0, 1.0],
+                program: None,
+            },
+            polygon: PolygonSymbol {
+                fill_color: [0.0, 0.0, 1.0, 1.0],
+                stroke_width: 1.0,
+                stroke_color: [0.0, 0.0, 0.0, 1.0],
+                program: None,
+            },
+            program: None,
+        };
+
+        let point = [0.0, 0.0, 0.0];
+        let (point_vertices, _) = Symbol::<Point3>::convert(&symbol.point, &point, 0, point);
+        let (vertices, indices) = symbol.convert(
+            &Geometry::GeometryCollection(vec![
+                Geometry::Point(point),
+                Geometry::LineString(vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]]),
+            ]),
+            0,
+            point,
+        );
+
+        // Point vertices go last, so that points are drawn on top of the line
+        let indices = indices.unwrap();
+        let first_point_vertex = (vertices.len() - point_vertices.len()) as u32;
+        assert!(first_point_vertex > 0);
+        assert!(indices[indices.len() - point_vertices.len()..]
+            .iter()
+            .all(|i| *i >= first_point_vertex && (*i as usize) < vertices.len()));
+    }
+}
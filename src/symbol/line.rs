@@ -1,10 +1,11 @@
+use crate::geometry::Geometry;
 use crate::gl::{AttributeValueType, Vertex, VertexAttribute};
-use crate::symbol::Symbol;
+use crate::symbol::{merge_parts, relative_position, Symbol};
 use crate::{Color, Point3, Polyline};
 use glow::Program;
 use lyon::lyon_tessellation::{BuffersBuilder, StrokeVertex};
 use lyon::math::point;
-use lyon::tessellation::path::builder::{Build, PathBuilder};
+use lyon::tessellation::path::Path;
 use lyon::tessellation::{
     StrokeOptions, StrokeTessellator, StrokeVertexConstructor, VertexBuffers,
 };
@@ -23,18 +24,24 @@ layout (location = 2) in uint id;
 
 uniform mat4 transformation;
 uniform uint mode;
+uniform float opacity;
 uniform vec2 screen_size;
 
 out vec4 frag_color;
 
 void main() {
     gl_Position = vec4(position.xyz, 1.0) * transformation;
-    frag_color = color;
     if (mode == 0u) {
-        frag_color = color;
+        frag_color = vec4(color.rgb, color.a * opacity);
     }
     if (mode == 1u) {
-        frag_color = vec4((float(id) + 1.0) / 255.0, 0.0, 0.0, 1.0);
+        uint value = id + 1u;
+        frag_color = vec4(
+            float(value & 255u),
+            float((value >> 8) & 255u),
+            float((value >> 16) & 255u),
+            255.0
+        ) / 255.0;
     }
 }
 "#;
@@ -56,10 +63,11 @@ pub struct VertexCtor {
 }
 
 impl StrokeVertexConstructor<LineVertex> for VertexCtor {
-    fn new_vertex(&mut self, vertex: StrokeVertex) -> LineVertex {
+    fn new_vertex(&mut self, mut vertex: StrokeVertex) -> LineVertex {
         let point = vertex.position();
+        let z = vertex_z(vertex.interpolated_attributes());
         LineVertex {
-            position: [point.x, point.y, 0.0],
+            position: [point.x, point.y, z],
             color: self.color,
             id: self.id,
         }
@@ -85,7 +93,12 @@ impl Symbol<Polyline> for LineSymbol {
         self.program.as_ref()
     }
 
-    fn convert(&self, geometry: &Polyline, id: u32) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+    fn convert(
+        &self,
+        geometry: &Polyline,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
         if geometry.len() < 2 {
             return (vec![], None);
         }
@@ -98,26 +111,88 @@ impl Symbol<Polyline> for LineSymbol {
                 id,
             },
         );
-        let mut tessellator = StrokeTessellator::new();
-        let options = StrokeOptions::default().with_line_width(self.width);
-        let mut builder = tessellator.builder(&options, &mut geometry_builder);
-
-        builder.begin(point(geometry[0][0], geometry[0][1]));
-        for p in geometry.iter().skip(1) {
-            builder.line_to(point(p[0], p[1]));
-        }
-        builder.end(false);
-
-        builder.build().unwrap();
+        let path = build_path(std::iter::once(geometry.as_slice()), &origin, false);
+        StrokeTessellator::new()
+            .tessellate_path(
+                &path,
+                &StrokeOptions::default().with_line_width(self.width),
+                &mut geometry_builder,
+            )
+            .unwrap();
 
         let VertexBuffers { vertices, indices } = buffers;
         (vertices, Some(indices))
     }
 }
 
+impl Symbol<Geometry> for LineSymbol {
+    type Vertex = LineVertex;
+
+    fn vertex_shader(&self) -> &str {
+        VERTEX_SHADER
+    }
+
+    fn fragment_shader(&self) -> &str {
+        FRAGMENT_SHADER
+    }
+
+    fn set_program(&mut self, program: Program) {
+        self.program = Some(program);
+    }
+
+    fn program(&self) -> Option<&Program> {
+        self.program.as_ref()
+    }
+
+    /// Converts the lines of the geometry, other parts are ignored.
+    fn convert(
+        &self,
+        geometry: &Geometry,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+        merge_parts(
+            geometry
+                .line_strings()
+                .into_iter()
+                .map(|part| Symbol::<Polyline>::convert(self, part, id, origin)),
+        )
+    }
+}
+
+/// Builds a path from the contours with the z coordinate of the points stored as a custom
+/// attribute, so that the tessellators can interpolate it for the vertices they create.
+pub(crate) fn build_path<'a>(
+    contours: impl IntoIterator<Item = &'a [Point3]>,
+    origin: &Point3,
+    closed: bool,
+) -> Path {
+    let mut path_builder = Path::builder_with_attributes(1);
+
+    for contour in contours {
+        let (first, rest) = match contour.split_first() {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let [x, y, z] = relative_position(first, origin);
+        path_builder.begin(point(x, y), &[z]);
+        for p in rest {
+            let [x, y, z] = relative_position(p, origin);
+            path_builder.line_to(point(x, y), &[z]);
+        }
+        path_builder.end(closed);
+    }
+
+    path_builder.build()
+}
+
+pub(crate) fn vertex_z(attributes: &[f32]) -> f32 {
+    attributes.first().copied().unwrap_or(0.0)
+}
+
 #[derive(Copy, Clone)]
 pub struct LineVertex {
-    pub position: Point3,
+    pub position: [f32; 3],
     pub color: Color,
     pub id: u32,
 }
@@ -143,3 +218,34 @@ impl Vertex for LineVertex {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z_is_interpolated_along_line() {
+        let symbol = LineSymbol {
+            width: 1.0,
+            color: [0.0, 0.0, 0.0, 1.0],
+            program: None,
+        };
+        let line = vec![[10.0, 0.0, 5.0], [15.0, 0.0, 10.0], [20.0, 0.0, 15.0]];
+        let (vertices, _) = symbol.convert(&line, 0, [10.0, 0.0, 5.0]);
+
+        assert!(!vertices.is_empty());
+        for vertex in vertices {
+            let [x, _, z] = vertex.position;
+            let expected = x;
+            assert!((z - expected).abs() < 1e-3, "{:?}", vertex.position);
+        }
+    }
+
+    #[test]
+    fn empty_contours_are_skipped() {
+        let line = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0]];
+        let contours = vec![&[][..], line.as_slice(), &[][..]];
+        let path = build_path(contours, &[0.0, 0.0, 0.0], true);
+        assert_eq!(3, path.iter().count());
+    }
+}
@@ -2,12 +2,16 @@ mod circle;
 pub use circle::*;
 
 mod line;
-pub use line::LineSymbol;
+pub use line::{LineSymbol, LineVertex};
 
 mod polygon;
 pub use polygon::PolygonSymbol;
 
+mod composite;
+pub use composite::CompositeSymbol;
+
 use crate::gl::Vertex;
+use crate::Point3;
 use glow::{Context, HasContext, Program};
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -72,9 +76,69 @@ pub trait Symbol<G> {
         self.program().is_some()
     }
     fn program(&self) -> Option<&Program>;
-    fn convert(&self, geometry: &G, id: u32) -> (Vec<Self::Vertex>, Option<Vec<u32>>);
+
+    /// Converts the geometry into vertices. Vertex positions are relative to the `origin`, so
+    /// that they can be stored with single precision without losing accuracy.
+    fn convert(
+        &self,
+        geometry: &G,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>);
+}
+
+/// Position of the point relative to the `origin` in single precision.
+pub(crate) fn relative_position(point: &Point3, origin: &Point3) -> [f32; 3] {
+    [
+        (point[0] - origin[0]) as f32,
+        (point[1] - origin[1]) as f32,
+        (point[2] - origin[2]) as f32,
+    ]
+}
+
+/// Merges vertices of the parts of a geometry into a single list. If some of the parts are
+/// indexed, indices are generated for the others as well.
+pub(crate) fn merge_parts<V>(
+    parts: impl IntoIterator<Item = (Vec<V>, Option<Vec<u32>>)>,
+) -> (Vec<V>, Option<Vec<u32>>) {
+    let parts: Vec<_> = parts.into_iter().collect();
+    let indexed = parts.iter().any(|(_, indices)| indices.is_some());
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    for (mut part_vertices, part_indices) in parts {
+        let offset = vertices.len() as u32;
+        if indexed {
+            match part_indices {
+                Some(part_indices) => indices.extend(part_indices.iter().map(|i| i + offset)),
+                None => indices.extend(offset..offset + part_vertices.len() as u32),
+            }
+        }
+        vertices.append(&mut part_vertices);
+    }
+
+    (vertices, if indexed { Some(indices) } else { None })
 }
 
 fn get_vertex_source(source: &str) -> String {
     format!("{}\n{}", GL_VERSION, source)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parts_merging() {
+        let (vertices, indices) = merge_parts(vec![
+            (vec![0, 1, 2], None),
+            (vec![3, 4, 5, 6], Some(vec![0, 1, 2, 1, 2, 3])),
+        ]);
+        assert_eq!(vec![0, 1, 2, 3, 4, 5, 6], vertices);
+        assert_eq!(Some(vec![0, 1, 2, 3, 4, 5, 4, 5, 6]), indices);
+
+        let (vertices, indices) = merge_parts(vec![(vec![0, 1, 2], None), (vec![3, 4, 5], None)]);
+        assert_eq!(6, vertices.len());
+        assert_eq!(None, indices);
+    }
+}
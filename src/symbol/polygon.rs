@@ -1,10 +1,9 @@
-use crate::symbol::line::{LineVertex, VertexCtor};
-use crate::symbol::Symbol;
-use crate::{Color, Polygon, PolygonRef};
+use crate::geometry::Geometry;
+use crate::symbol::line::{build_path, vertex_z, LineVertex, VertexCtor};
+use crate::symbol::{merge_parts, Symbol};
+use crate::{Color, Point3, Polygon};
 use glow::Program;
 use lyon::lyon_tessellation::BuffersBuilder;
-use lyon::math::point;
-use lyon::tessellation::path::Path;
 use lyon::tessellation::{
     FillOptions, FillRule, FillTessellator, FillVertex, FillVertexConstructor, StrokeOptions,
     StrokeTessellator, VertexBuffers,
@@ -24,15 +23,22 @@ layout (location = 2) in uint id;
 
 uniform mat4 transformation;
 uniform uint mode;
+uniform float opacity;
 uniform vec2 screen_size;
 
 out vec4 frag_color;
 
 void main() {
     gl_Position = vec4(position.xyz, 1.0) * transformation;
-    frag_color = color;
+    frag_color = vec4(color.rgb, color.a * opacity);
     if (mode == 1u) {
-        frag_color = vec4((float(id) + 1.0) / 255.0, 0.0, 0.0, 1.0);
+        uint value = id + 1u;
+        frag_color = vec4(
+            float(value & 255u),
+            float((value >> 8) & 255u),
+            float((value >> 16) & 255u),
+            255.0
+        ) / 255.0;
     }
 }
 "#;
@@ -49,10 +55,11 @@ void main() {
 "#;
 
 impl FillVertexConstructor<LineVertex> for VertexCtor {
-    fn new_vertex(&mut self, vertex: FillVertex) -> LineVertex {
+    fn new_vertex(&mut self, mut vertex: FillVertex) -> LineVertex {
         let point = vertex.position();
+        let z = vertex_z(vertex.interpolated_attributes());
         LineVertex {
-            position: [point.x, point.y, 0.0],
+            position: [point.x, point.y, z],
             color: self.color,
             id: self.id,
         }
@@ -78,8 +85,13 @@ impl Symbol<Polygon> for PolygonSymbol {
         self.program.as_ref()
     }
 
-    fn convert(&self, geometry: &Polygon, id: u32) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
-        let path = build_geometry(geometry);
+    fn convert(
+        &self,
+        geometry: &Polygon,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+        let path = build_path(geometry.iter().map(Vec::as_slice), &origin, true);
         let mut buffers: VertexBuffers<LineVertex, u32> = VertexBuffers::new();
 
         let mut fill_vertex_builder = BuffersBuilder::new(
@@ -121,16 +133,63 @@ impl Symbol<Polygon> for PolygonSymbol {
     }
 }
 
-fn build_geometry(geometry: &PolygonRef) -> Path {
-    let mut path_builder = Path::builder();
+impl Symbol<Geometry> for PolygonSymbol {
+    type Vertex = LineVertex;
 
-    for contour in geometry {
-        path_builder.begin(point(contour[0][0], contour[0][1]));
-        for p in contour.iter().skip(1) {
-            path_builder.line_to(point(p[0], p[1]));
-        }
-        path_builder.end(true);
+    fn vertex_shader(&self) -> &str {
+        VERTEX_SHADER
+    }
+
+    fn fragment_shader(&self) -> &str {
+        FRAGMENT_SHADER
     }
 
-    path_builder.build()
+    fn set_program(&mut self, program: Program) {
+        self.program = Some(program);
+    }
+
+    fn program(&self) -> Option<&Program> {
+        self.program.as_ref()
+    }
+
+    /// Converts the polygons of the geometry, other parts are ignored.
+    fn convert(
+        &self,
+        geometry: &Geometry,
+        id: u32,
+        origin: Point3,
+    ) -> (Vec<Self::Vertex>, Option<Vec<u32>>) {
+        merge_parts(
+            geometry
+                .polygons()
+                .into_iter()
+                .map(|part| Symbol::<Polygon>::convert(self, part, id, origin)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn z_is_kept_for_fill_and_stroke() {
+        let symbol = PolygonSymbol {
+            fill_color: [1.0, 0.0, 0.0, 1.0],
+            stroke_width: 1.0,
+            stroke_color: [0.0, 0.0, 0.0, 1.0],
+            program: None,
+        };
+        let polygon = vec![vec![
+            [0.0, 0.0, 10.0],
+            [10.0, 0.0, 10.0],
+            [10.0, 10.0, 10.0],
+            [0.0, 10.0, 10.0],
+        ]];
+        let (vertices, _) = symbol.convert(&polygon, 0, [0.0, 0.0, 4.0]);
+
+        assert!(vertices.iter().any(|v| v.color == symbol.fill_color));
+        assert!(vertices.iter().any(|v| v.color == symbol.stroke_color));
+        assert!(vertices.iter().all(|v| v.position[2] == 6.0));
+    }
 }